@@ -0,0 +1,320 @@
+use chrono::Local;
+
+use crate::models::{Boat, TransportGroup, Venue, StrategyKind, Leg};
+use crate::models::edit::TransportPlan;
+use crate::models::venue::venue_label;
+use crate::models::expenses::{ExpenseReport, ExpenseRates, parse_range, pounds};
+use crate::models::itinerary::itineraries;
+use crate::state::SystemState;
+use crate::export::ExportFormat;
+
+const USAGE: &str = "\
+Usage: Transport <COMMAND> <SESSION_FILE> [OPTIONS]
+
+Commands:
+  validate <SESSION_FILE>                Check the session's crews against the squad without allocating
+  allocate <SESSION_FILE>                Run the transport allocation and print the result
+  pdf      <SESSION_FILE> [--out FILE]   Run the allocation, write the transport sheet PDF and record who drove
+           [--slips FILE]                Also write a slip for each driver (PDF)
+           [--itineraries FILE]          Also write everyone's itinerary (PDF, or a line each if FILE isn't .pdf)
+  export   <SESSION_FILE> [--format F]   Run the allocation and write the vehicles as csv (default), json, markdown
+           [--out FILE]                  (for chat messages) or ics (calendar events for the drivers)
+  expenses [--from DATE] [--to DATE]     Write the mileage claims for car journeys on published sheets
+           [--out FILE]                  (CSV, or PDF if FILE ends in .pdf). Dates are YYYY-MM-DD and default
+                                         to the start of this month and today
+  help                                   Show this message
+
+Options:
+  --strategy NAME                        Allocation strategy: greedy (default), fewest-vehicles,
+                                         fewest-minibus-drivers, keep-crews-together or balanced-integration
+  --format FORMAT                        Export format for 'export': csv, json, markdown (or md) or ics
+  --date YYYY-MM-DD                      Day of the outing, which drivers' licences and insurance are checked
+                                         against and exports are dated by (defaults to the session's date, or today)
+  --partial                              Still print / publish the plan when someone can't be seated,
+                                         listing them under \"Needs transport\" (exits 0)
+  --fair                                 Fair rota: hand each vehicle to whoever on board has driven least
+                                         recently (from driver_history.json)
+  --rate POUNDS                          Mileage rate per mile for 'expenses' (default 0.45)
+  --passenger-rate POUNDS                Extra per mile for each passenger for 'expenses' (default 0.05)
+
+Running with no command opens the app window as normal.";
+
+// --- ENTRY POINT FOR HEADLESS MODE - RETURNS THE PROCESS EXIT CODE ---
+pub fn run(args: &[String], state: &mut SystemState, load_error: Option<String>) -> i32 {
+    let command = args[0].as_str();
+
+    if matches!(command, "help" | "--help" | "-h") {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    // Working from a squad with people missing would give a plan that looks right but isn't
+    if let Some(msg) = load_error {
+        eprintln!("{}", msg);
+        return 1;
+    }
+
+    if command == "expenses" {
+        return expenses(args, state);
+    }
+
+    if !matches!(command, "validate" | "allocate" | "pdf" | "export") {
+        eprintln!("Unknown command '{}'.\n\n{}", command, USAGE);
+        return 2;
+    }
+
+    // Every command needs a session (or plain crew list) to work from
+    let Some(session_file) = args.get(1) else {
+        eprintln!("Missing session file for '{}'.\n\n{}", command, USAGE);
+        return 2;
+    };
+
+    if let Err(e) = state.load_session(session_file) {
+        eprintln!("Could not read session file '{}': {}", session_file, e);
+        return 1;
+    }
+
+    // The outing date can be given on the command line, overriding the one saved with the session
+    if let Some(date) = option_value(args, "--date") {
+        state.session.date = date;
+    }
+    let boats = &state.session.boats;
+
+    // Same checks as the "Publish & PDF" button before anything gets allocated
+    if let Err(msg) = state.validate_boats(boats) {
+        eprintln!("{}", msg);
+        return 1;
+    }
+
+    let strategy = match option_value(args, "--strategy") {
+        None => StrategyKind::default(),
+        Some(name) => match StrategyKind::from_name(&name) {
+            Some(kind) => kind,
+            None => {
+                eprintln!("Unknown strategy '{}'.\n\n{}", name, USAGE);
+                return 2;
+            }
+        },
+    };
+
+    let format = match option_value(args, "--format") {
+        None => ExportFormat::default(),
+        Some(name) => match ExportFormat::from_name(&name) {
+            Some(format) => format,
+            None => {
+                eprintln!("Unknown format '{}'.\n\n{}", name, USAGE);
+                return 2;
+            }
+        },
+    };
+
+    let partial = args.iter().any(|a| a == "--partial");
+    let fair    = args.iter().any(|a| a == "--fair");
+
+    match command {
+        "validate" => {
+            println!("{} boat(s) OK.", boats.len());
+            0
+        }
+        "allocate" => match allocate(boats, state, strategy, partial, fair) {
+            Ok(plan) => {
+                print_groups(&plan.groups, &state.venues);
+                print_unseated(&plan);
+                0
+            }
+            Err(code) => code,
+        },
+        "export" => {
+            // Dated by the outing, so a plan exported ahead of time lands on the right day
+            let on  = state.session.outing_date();
+            let out = option_value(args, "--out")
+                .unwrap_or_else(|| format!("transport_{}.{}", on.format("%Y-%m-%d"), format.extension()));
+
+            let plan = match allocate(boats, state, strategy, partial, fair) {
+                Ok(plan) => plan,
+                Err(code) => return code,
+            };
+
+            let text = format.export(&plan.groups, &state.venues, on);
+            if let Err(e) = std::fs::write(&out, text) {
+                eprintln!("Could not write '{}': {}", out, e);
+                return 1;
+            }
+            println!("Wrote {}", out);
+            0
+        }
+        _ => {
+            // Defaults to the same file name the app uses when publishing, dated by the outing
+            let on  = state.session.outing_date();
+            let out = option_value(args, "--out")
+                .unwrap_or_else(|| format!("transport_sheet_{}.pdf", on.format("%Y-%m-%d")));
+
+            let plan = match allocate(boats, state, strategy, partial, fair) {
+                Ok(plan) => plan,
+                Err(code) => return code,
+            };
+
+            if let Err(e) = crate::pdf::generate_pdf(&plan, &state.session.boats, &state.people, &state.venues, &state.theme, on, &out) {
+                eprintln!("PDF Generation failed: {}", e);
+                return 1;
+            }
+            println!("Wrote {}", out);
+
+            // Driver slips and itineraries come from the same plan as the sheet
+            if let Some(slips) = option_value(args, "--slips") {
+                if let Err(e) = crate::pdf::generate_driver_slips(&plan, &state.venues, &state.pickup_points, &state.theme, on, &slips) {
+                    eprintln!("Could not write the driver slips: {}", e);
+                    return 1;
+                }
+                println!("Wrote {}", slips);
+            }
+            if let Some(file) = option_value(args, "--itineraries") {
+                let itineraries = itineraries(&plan, &state.venues);
+                let written = if file.to_lowercase().ends_with(".pdf") {
+                    crate::pdf::generate_itinerary_pdf(&itineraries, &state.theme, on, &file)
+                } else {
+                    let lines: Vec<String> = itineraries.iter().map(|i| i.line()).collect();
+                    std::fs::write(&file, lines.join("\n") + "\n").map_err(|e| e.into())
+                };
+                if let Err(e) = written {
+                    eprintln!("Could not write the itineraries: {}", e);
+                    return 1;
+                }
+                println!("Wrote {}", file);
+            }
+
+            // A published sheet goes into the driving history, the same as from the app
+            if let Err(e) = state.record_drives(&plan.groups, on) {
+                eprintln!("Could not save the driving history: {}", e);
+            }
+            0
+        }
+    }
+}
+
+// --- HANDS THE CREWS TO THE ALLOCATION ALGORITHM AND REPORTS ANYONE LEFT OVER ---
+// With `partial` the groups that could be made are still returned, along with who still needs transport
+fn allocate(boats: &[Boat], state: &SystemState, strategy: StrategyKind, partial: bool, fair: bool) -> Result<TransportPlan, i32> {
+    let requests = state.transport_requests(boats);
+    let outcome  = strategy.run(requests, &state.minibuses, &state.session, &state.travel_matrix(), fair.then_some(&state.history));
+
+    for line in &outcome.diagnostics {
+        println!("{}", line);
+    }
+
+    if let Err(report) = &outcome.result {
+        eprintln!("{}", report);
+        if !partial { return Err(1); }
+    }
+    Ok(TransportPlan {
+        support_staff: state.support_trips(),
+        ..TransportPlan::from_result(outcome.result, Vec::new(), state.making_own_way(boats))
+    })
+}
+
+// --- MILEAGE CLAIMS FROM THE DRIVING HISTORY (NO SESSION NEEDED) ---
+fn expenses(args: &[String], state: &SystemState) -> i32 {
+    let today = Local::now().date_naive();
+    let from  = option_value(args, "--from").unwrap_or_else(|| today.format("%Y-%m-01").to_string());
+    let to    = option_value(args, "--to").unwrap_or_else(|| today.format("%Y-%m-%d").to_string());
+    let defaults = ExpenseRates::default();
+
+    let range = parse_range(&from, &to);
+    let rates = ExpenseRates::parse(
+        &option_value(args, "--rate").unwrap_or(pounds(defaults.per_mile)),
+        &option_value(args, "--passenger-rate").unwrap_or(pounds(defaults.per_passenger_mile)),
+    );
+    let ((from, to), rates) = match (range, rates) {
+        (Ok(range), Ok(rates)) => (range, rates),
+        (Err(msg), _) | (_, Err(msg)) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            return 2;
+        }
+    };
+
+    let report = ExpenseReport::build(&state.history, from, to, rates, &state.travel_matrix());
+    let out    = option_value(args, "--out").unwrap_or_else(|| format!("mileage_{}_to_{}.csv", from, to));
+
+    let written = if out.to_lowercase().ends_with(".pdf") {
+        crate::pdf::generate_expense_pdf(&report, &state.theme, &out)
+    } else {
+        std::fs::write(&out, report.to_csv()).map_err(|e| e.into())
+    };
+    if let Err(e) = written {
+        eprintln!("Could not write the mileage claims: {}", e);
+        return 1;
+    }
+
+    for claim in &report.claims {
+        println!("{}: {:.1} miles, £{}", claim.name, claim.miles(), pounds(claim.total(&report.rates)));
+    }
+    println!("Wrote {} ({} driver(s), £{} in total)", out, report.claims.len(), pounds(report.total()));
+    0
+}
+
+// --- PEOPLE IN THE CREWS WHO AREN'T IN ANY VEHICLE ---
+fn print_unseated(plan: &TransportPlan) {
+    if !plan.needs_transport.is_empty() {
+        println!("== Needs transport ==");
+        for shortfall in &plan.needs_transport {
+            for p in &shortfall.people {
+                println!("  - {} ({} {})", p.name, shortfall.departure_time, shortfall.venue_name);
+            }
+        }
+    }
+    if !plan.making_own_way.is_empty() {
+        println!("== Making own way ==");
+        for o in &plan.making_own_way {
+            println!("  - {} ({} {})", o.person.name, o.departure_time, o.venue_name);
+        }
+    }
+    if !plan.support_staff.is_empty() {
+        println!("== Support staff ==");
+        for s in &plan.support_staff {
+            let vehicle = plan.outbound_vehicle(&s.person.student_id).map_or("no vehicle", |g| g.vehicle_label.as_str());
+            println!("  - {} [{}] ({} {}) - {}", s.person.name, s.role.label(), s.departure_time, s.venue_name, vehicle);
+        }
+    }
+}
+
+// --- PRINTS ONE BLOCK PER VEHICLE IN THE SAME ORDER AS THE TRANSPORT SHEET COLUMNS (OUTBOUND, THEN RETURN) ---
+fn print_groups(groups: &[TransportGroup], venues: &[Venue]) {
+    for (leg, heading) in [(Leg::Outbound, "Outbound"), (Leg::Return, "Return")] {
+        println!("== {} ==", heading);
+        for group in groups.iter().filter(|g| g.leg == leg) {
+            print_group(group, venues);
+        }
+    }
+}
+
+fn print_group(group: &TransportGroup, venues: &[Venue]) {
+    let venue = venue_label(venues, &group.destination);
+    match group.leg {
+        Leg::Outbound => println!("{} {} - {}", group.departure_time, venue, group.vehicle_label),
+        Leg::Return => println!("{} from {} - {}", group.departure_time, venue, group.vehicle_label),
+    }
+    println!("  Driver: {}", group.driver.name);
+    if !group.equipment.is_empty() {
+        println!("  Carrying: {}", group.load_summary());
+    }
+    for p in &group.passengers {
+        println!("  - {}", p.name);
+    }
+    for stop in &group.stops {
+        println!("  {} {} ({})", stop.time, stop.location, stop.people.join(", "));
+    }
+    if !group.arrival_time.is_empty() {
+        match group.leg {
+            Leg::Outbound => println!("  Arrive {}", group.arrival_time),
+            Leg::Return => println!("  Back {}", group.arrival_time),
+        }
+    }
+}
+
+// --- LOOKS UP THE VALUE FOLLOWING A FLAG (e.g. "--out sheet.pdf") ---
+fn option_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
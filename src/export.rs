@@ -0,0 +1,258 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Duration, Utc};
+use serde::Serialize;
+use crate::models::{TransportGroup, Venue, Leg, Person, PickupStop};
+use crate::models::expenses::csv_field;
+use crate::models::venue::{venue_label, find_venue};
+
+// --- THE FORMATS THE VEHICLES CAN BE EXPORTED IN, BESIDES THE PDF (PICKED IN THE TRANSPORT TAB OR WITH --format) ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Csv,                                    // One row per person, for the shared spreadsheet
+    Json,                                   // Every vehicle with its stops, for other tools
+    Markdown,                               // Plain text with *bold* headings, for pasting into a group chat
+    Ics,                                    // A calendar event for each vehicle, for its driver
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [ExportFormat::Csv, ExportFormat::Json, ExportFormat::Markdown, ExportFormat::Ics];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Ics => "Calendar (.ics)",
+        }
+    }
+
+    // Name used by the command line "--format" option, and the file extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Ics => "ics",
+        }
+    }
+
+    // Accepts the extension or the format's name (case insensitive, so "markdown" and "md" both work)
+    pub fn from_name(name: &str) -> Option<ExportFormat> {
+        Self::ALL.into_iter().find(|f| {
+            f.extension().eq_ignore_ascii_case(name) || format!("{:?}", f).eq_ignore_ascii_case(name)
+        })
+    }
+
+    // --- THE VEHICLES ON `date`, WRITTEN OUT IN THIS FORMAT ---
+    pub fn export(&self, groups: &[TransportGroup], venues: &[Venue], date: NaiveDate) -> String {
+        match self {
+            ExportFormat::Csv => to_csv(groups, venues, date),
+            ExportFormat::Json => to_json(groups, venues, date),
+            ExportFormat::Markdown => to_markdown(groups, venues, date),
+            ExportFormat::Ics => to_ics(groups, venues, date),
+        }
+    }
+}
+
+// --- EVERYONE IN A VEHICLE IN THE ORDER THEY GET IN (OR OUT), WITH THEIR STOP ---
+// Anyone not found at a stop (the stops are matched by name) goes at the end without one
+fn riders(group: &TransportGroup) -> Vec<(&Person, Option<&PickupStop>)> {
+    let everyone: Vec<&Person> = std::iter::once(&group.driver).chain(&group.passengers).collect();
+    let mut riders: Vec<(&Person, Option<&PickupStop>)> = Vec::new();
+
+    for stop in &group.stops {
+        for name in &stop.people {
+            if let Some(person) = everyone.iter().find(|p| p.name == *name && !riders.iter().any(|(r, _)| r.student_id == p.student_id)) {
+                riders.push((person, Some(stop)));
+            }
+        }
+    }
+    for person in everyone {
+        if !riders.iter().any(|(r, _)| r.student_id == person.student_id) {
+            riders.push((person, None));
+        }
+    }
+    riders
+}
+
+fn leg_label(leg: Leg) -> &'static str {
+    if leg == Leg::Return { "Return" } else { "Outbound" }
+}
+
+// --- CSV: ONE ROW PER PERSON, VEHICLE BY VEHICLE ---
+fn to_csv(groups: &[TransportGroup], venues: &[Venue], date: NaiveDate) -> String {
+    let mut lines = vec![
+        "Date,Leg,Departure,Venue,Vehicle,Registration,Driver,Name,Student ID,Role,Phone,Stop,Stop time,Arrival".to_string(),
+    ];
+
+    for group in groups {
+        let venue = venue_label(venues, &group.destination);
+        for (person, stop) in riders(group) {
+            let role = if person.student_id == group.driver.student_id { "Driver" } else { "Passenger" };
+            lines.push([
+                date.format("%Y-%m-%d").to_string(), leg_label(group.leg).to_string(), csv_field(&group.departure_time),
+                csv_field(venue), csv_field(&group.vehicle_label), csv_field(&group.registration), csv_field(&group.driver.name),
+                csv_field(&person.name), csv_field(&person.student_id), role.to_string(), csv_field(&person.phone),
+                csv_field(stop.map_or("", |s| s.location.as_str())), csv_field(stop.map_or("", |s| s.time.as_str())),
+                csv_field(&group.arrival_time),
+            ].join(","));
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+// --- JSON: THE SAME VEHICLES, KEEPING ONLY WHAT'S NEEDED TO GET PEOPLE THERE (NO LICENCE DETAILS) ---
+#[derive(Serialize)]
+struct ExportedPlan {
+    date: String,
+    vehicles: Vec<ExportedVehicle>,
+}
+
+#[derive(Serialize)]
+struct ExportedVehicle {
+    leg: Leg,
+    vehicle: String,
+    registration: String,
+    capacity: usize,
+    venue: String,
+    venue_address: String,
+    departure_time: String,
+    arrival_time: String,
+    driver: ExportedPerson,
+    passengers: Vec<ExportedPerson>,
+    stops: Vec<PickupStop>,
+    equipment: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ExportedPerson {
+    name: String,
+    student_id: String,
+    phone: String,
+}
+
+impl ExportedPerson {
+    fn from(person: &Person) -> Self {
+        ExportedPerson { name: person.name.clone(), student_id: person.student_id.clone(), phone: person.phone.clone() }
+    }
+}
+
+fn to_json(groups: &[TransportGroup], venues: &[Venue], date: NaiveDate) -> String {
+    let plan = ExportedPlan {
+        date: date.format("%Y-%m-%d").to_string(),
+        vehicles: groups.iter().map(|g| ExportedVehicle {
+            leg:            g.leg,
+            vehicle:        g.vehicle_label.clone(),
+            registration:   g.registration.clone(),
+            capacity:       g.capacity,
+            venue:          venue_label(venues, &g.destination).to_string(),
+            venue_address:  find_venue(venues, &g.destination).map(|v| v.address.clone()).unwrap_or_default(),
+            departure_time: g.departure_time.clone(),
+            arrival_time:   g.arrival_time.clone(),
+            driver:         ExportedPerson::from(&g.driver),
+            passengers:     g.passengers.iter().map(ExportedPerson::from).collect(),
+            stops:          g.stops.clone(),
+            equipment:      g.equipment.iter().map(|l| l.kind.describe(l.quantity)).collect(),
+        }).collect(),
+    };
+    serde_json::to_string_pretty(&plan).unwrap() + "\n"
+}
+
+// --- PLAIN TEXT FOR A GROUP CHAT: A BOLD LINE PER VEHICLE, THEN WHO GETS IN WHERE ---
+fn to_markdown(groups: &[TransportGroup], venues: &[Venue], date: NaiveDate) -> String {
+    let mut lines = vec![format!("*Transport - {}*", date.format("%A %-d %B"))];
+
+    for (leg, heading) in [(Leg::Outbound, "Getting there"), (Leg::Return, "Getting home")] {
+        let mut vehicles = groups.iter().filter(|g| g.leg == leg).peekable();
+        if vehicles.peek().is_none() { continue; }
+
+        lines.push(String::new());
+        lines.push(format!("*{}*", heading));
+        for group in vehicles {
+            let venue = venue_label(venues, &group.destination);
+            lines.push(String::new());
+            lines.push(match leg {
+                Leg::Outbound => format!("*{} {} - {}*", group.departure_time, venue, group.vehicle_label),
+                Leg::Return => format!("*{} from {} - {}*", group.departure_time, venue, group.vehicle_label),
+            });
+            lines.push(format!("Driver: {}", group.driver.name));
+            if !group.equipment.is_empty() {
+                lines.push(format!("Carrying {}", group.load_summary()));
+            }
+            for stop in &group.stops {
+                lines.push(format!("- {} {}: {}", stop.time, stop.location, stop.people.join(", ")));
+            }
+            if !group.arrival_time.is_empty() {
+                let label = if leg == Leg::Return { "Back" } else { "Arrive" };
+                lines.push(format!("{} {}", label, group.arrival_time));
+            }
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+// --- ICALENDAR: AN EVENT FOR EACH VEHICLE, SORTED BY DRIVER, FROM ITS FIRST STOP UNTIL IT ARRIVES ---
+// Times are local ("floating"), the same as everywhere else in the app
+fn to_ics(groups: &[TransportGroup], venues: &[Venue], date: NaiveDate) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VCALENDAR".into(), "VERSION:2.0".into(), "PRODID:-//Rowing Transport Manager//EN".into(), "CALSCALE:GREGORIAN".into(),
+    ];
+
+    let mut driven: Vec<&TransportGroup> = groups.iter().collect();
+    driven.sort_by(|a, b| a.driver.name.cmp(&b.driver.name));
+
+    for group in driven {
+        let time  = |text: &str| NaiveTime::parse_from_str(text.trim(), "%H:%M").ok().map(|t| NaiveDateTime::new(date, t));
+        let first = group.stops.first().filter(|_| group.leg == Leg::Outbound).map_or(group.departure_time.as_str(), |s| s.time.as_str());
+        let Some(start) = time(first).or_else(|| time(&group.departure_time)) else { continue };
+        let end = time(&group.arrival_time).filter(|end| *end > start).unwrap_or(start + Duration::hours(1));
+
+        let venue   = venue_label(venues, &group.destination);
+        let summary = match group.leg {
+            Leg::Outbound => format!("{} drives {} to {}", group.driver.name, group.vehicle_label, venue),
+            Leg::Return => format!("{} drives {} home from {}", group.driver.name, group.vehicle_label, venue),
+        };
+        let mut details: Vec<String> = group.stops.iter()
+            .map(|s| format!("{} {}: {}", s.time, s.location, s.people.join(", ")))
+            .collect();
+        if !group.equipment.is_empty() {
+            details.push(format!("Carrying {}", group.load_summary()));
+        }
+        let location = find_venue(venues, &group.destination).map(|v| v.address.as_str()).filter(|a| !a.is_empty()).unwrap_or(venue);
+
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}-{}-{}-{}@rowing-transport", date.format("%Y%m%d"), group.registration.replace(' ', ""), leg_label(group.leg), start.format("%H%M")),
+            format!("DTSTAMP:{}", stamp),
+            format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")),
+            format!("DTEND:{}", end.format("%Y%m%dT%H%M%S")),
+            format!("SUMMARY:{}", ics_text(&summary)),
+            format!("LOCATION:{}", ics_text(location)),
+            format!("DESCRIPTION:{}", ics_text(&details.join("\n"))),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".into());
+    lines.iter().map(|l| fold(l)).collect::<Vec<_>>().join("\r\n") + "\r\n"
+}
+
+// Backslashes, commas, semicolons and line breaks are escaped in iCalendar text
+fn ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(',', "\\,").replace(';', "\\;").replace('\n', "\\n")
+}
+
+// Lines longer than 75 bytes carry on over the next line, which starts with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width  = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(ch);
+        width += ch.len_utf8();
+    }
+    folded
+}
@@ -2,6 +2,8 @@ mod models;
 mod state;
 mod ui;
 mod pdf;
+mod export;
+mod cli;
 
 use crate::ui::{RowingApp};
 use crate::state::SystemState;
@@ -9,24 +11,43 @@ use crate::state::SystemState;
 fn main() -> eframe::Result<()> {
     let mut state = SystemState::default();
 
-    // --- GETTING INPUT DATA FROM THE JSON FILES ---
-    state.load_all().ok();
-    
+    // --- GETTING INPUT DATA FROM THE JSON FILES (any that can't be read are reported rather than saved over) ---
+    let load_error = state.load_all().err();
+
+    // --- HEADLESS MODE (any command line arguments skip the window entirely) ---
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args, &mut state, load_error));
+    }
+
     // --- GEENERATE TRANSPORT SHEET ---
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
         "Rowing Transport Manager",
         native_options,
-        Box::new(|_cc| Box::new(RowingApp { 
-            state, 
-            boats: vec![], 
+        Box::new(|_cc| Box::new(RowingApp {
+            state,
             selected_id: None,
-            error_message: None,
+            error_message: load_error,
             show_add_person: false,
             add_person_form: Default::default(),
             edit_person_form: None,
             edit_minibus_form: None,
-            wants_to_drive: std::collections::HashSet::new(),
+            edit_venue_form: None,
+            edit_pickup_form: None,
+            save_as_name: None,
+            show_open_session: false,
+            strategy: Default::default(),
+            publish_partial: false,
+            show_transport: false,
+            transport_plan: None,
+            dragging: None,
+            constraint_form: None,
+            equipment_form: None,
+            fair_rota: false,
+            show_driving_stats: false,
+            expense_form: None,
+            export_format: Default::default(),
         })),
     )
-}
\ No newline at end of file
+}
@@ -1,66 +1,169 @@
-use crate::models::{Person, Minibus, Gender, Destination};
-use std::collections::{HashMap, HashSet};
+use crate::models::{Person, Minibus, Gender, Destination, Session, PickupStop, TravelMatrix, FleetSchedule};
+use crate::models::route::plan_route;
+use crate::models::strategy::{AllocationStrategy, AllocationOutcome};
+use crate::models::report::AllocationReport;
+use crate::models::equipment::{Haulage, Load, EquipmentKind, DepartureKit};
+use crate::models::credentials::Licensed;
+use std::collections::HashSet;
+use serde::{Serialize, Deserialize};
+
+// --- WHICH WAY A VEHICLE IS GOING ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Leg {
+    #[default]
+    Outbound,                               // Pickup points -> venue (`departure_time` is when it leaves the last stop)
+    Return,                                 // Venue -> drop-off points (`departure_time` is when it leaves the venue)
+}
 
 #[derive(Debug, Clone)]
 pub struct TransportGroup {
     pub driver: Person,
     pub vehicle_label: String,
+    pub registration: String,               // Minibus or car registration (identifies the vehicle across departures)
     pub passengers: Vec<Person>,
     pub capacity: usize,
     pub pickup_location: String,
     pub destination: Destination,
     pub departure_time: String,
+    pub stops: Vec<PickupStop>,             // Ordered pickup stops with the time the vehicle leaves each one
+    pub arrival_time: String,               // Estimated arrival at the venue (or at the last drop-off on the way back)
+    pub leg: Leg,
+    pub kit: Haulage,                       // What the vehicle can tow and carry
+    pub equipment: Vec<Load>,               // Trailer and cargo it's taking (cargo past the boot uses up seats)
 }
 
-pub struct Allocation;
+impl TransportGroup {
 
-impl Allocation {
-    pub fn assign_transport_global(
+    // --- AN EMPTY MINIBUS LEAVING FROM ITS DEPOT ---
+    pub fn minibus(driver: Person, mb: &Minibus, dest: &Destination, time: &str) -> Self {
+        TransportGroup {
+            driver,
+            vehicle_label: format!("Minibus {}", mb.registration),
+            registration: mb.registration.clone(),
+            passengers: Vec::new(),
+            capacity: mb.seats as usize,
+            pickup_location: mb.depot.clone(),
+            destination: dest.clone(),
+            departure_time: time.to_string(),
+            stops: Vec::new(),
+            arrival_time: String::new(),
+            leg: Leg::Outbound,
+            kit: mb.kit,
+            equipment: Vec::new(),
+        }
+    }
+
+    // --- AN EMPTY CAR LEAVING FROM ITS OWNER'S FIRST PICKUP POINT (DRIVER MUST HAVE A CAR) ---
+    pub fn car(driver: Person, dest: &Destination, time: &str) -> Self {
+        let car = driver.car.clone().unwrap();
+        TransportGroup {
+            vehicle_label: format!("Car {} ({})", car.registration, car.vehicle_type),
+            registration: car.registration.clone(),
+            capacity: car.seats as usize,
+            pickup_location: driver.pickup_locations.first().cloned().unwrap_or("Home".to_string()),
+            driver,
+            passengers: Vec::new(),
+            destination: dest.clone(),
+            departure_time: time.to_string(),
+            stops: Vec::new(),
+            arrival_time: String::new(),
+            leg: Leg::Outbound,
+            kit: car.kit,
+            equipment: Vec::new(),
+        }
+    }
+
+    pub fn is_minibus(&self) -> bool {
+        self.vehicle_label.starts_with("Minibus")
+    }
+
+    pub fn free_seats(&self) -> usize {
+        self.capacity.saturating_sub(1 + self.passengers.len() + self.cargo_seats())
+    }
+
+    pub fn is_towing(&self) -> bool {
+        self.equipment.iter().any(|l| l.kind == EquipmentKind::Trailer)
+    }
+
+    pub fn cargo_slots(&self) -> usize {
+        self.equipment.iter().map(|l| l.kind.slots() * l.quantity).sum()
+    }
+
+    // --- SEATS TAKEN UP BY CARGO THAT DIDN'T FIT IN THE BOOT ---
+    pub fn cargo_seats(&self) -> usize {
+        self.cargo_slots().saturating_sub(self.kit.cargo_slots as usize)
+    }
+
+    // How many more seats this many more cargo slots would take
+    pub fn extra_seats_for(&self, slots: usize) -> usize {
+        (self.cargo_slots() + slots).saturating_sub(self.kit.cargo_slots as usize) - self.cargo_seats()
+    }
+
+    pub fn add_load(&mut self, kind: EquipmentKind) {
+        match self.equipment.iter_mut().find(|l| l.kind == kind) {
+            Some(load) => load.quantity += 1,
+            None => self.equipment.push(Load { kind, quantity: 1 }),
+        }
+    }
+
+    // --- e.g. "trailer, 8 sets of blades" ---
+    pub fn load_summary(&self) -> String {
+        self.equipment.iter().map(|l| l.kind.describe(l.quantity)).collect::<Vec<_>>().join(", ")
+    }
+}
+
+// --- THE ORIGINAL GREEDY PASS (LARGEST MINIBUS FIRST, THEN LARGEST CAR) ---
+pub struct Greedy;
+
+impl AllocationStrategy for Greedy {
+    fn allocate(
+        &self,
         requests: Vec<(Destination, String, Vec<Person>)>,  // List of (Destination, Departure Time, People Requesting Transport)
         minibuses: &[Minibus],                              // List of available minibuses with their capacities
-        wants_to_drive: &HashSet<String>,                   // Set of student IDs who have opted in to drive
-    ) -> Result<Vec<TransportGroup>, Vec<String>> {
+        session: &Session,                                  // Session being published (holds who has opted in to drive)
+        matrix: &TravelMatrix,                              // Travel times between pickup points and venues for route planning
+        licensed: &Licensed,                                // Who may drive a minibus or their own car on the day
+    ) -> AllocationOutcome {
         
         // Final list of transport groups to be returned
         let mut final_allocations = Vec::new();
 
-        // Collecing people from the requests
-        let mut all_requested_people: Vec<Person> = Vec::new();
-        for (_, _, people) in &requests {
-            all_requested_people.extend(people.clone());
-        }
+        // Keeping the requests so anyone left over can be explained at the end
+        let all_requests = requests.clone();
         
-        let mut available_minibuses = minibuses.to_vec();
-
-        // Sort minibuses by capacity descending to create a priority queue for larger vehicles
-        available_minibuses.sort_by(|a, b| b.seats.cmp(&a.seats));
+        // Minibuses sorted by capacity descending to create a priority queue for larger vehicles, and booked
+        // per departure so one back from an early wave can take a later one
+        let mut schedule = FleetSchedule::new(minibuses);
 
         for (dest, time, mut group_people) in requests {
-            
+
+            // The departure's trailer and cargo go on each vehicle as it's chosen, before its passengers
+            let mut kit = DepartureKit::for_departure(&session.equipment, &dest, &time);
+
             // --- FILLING MINIBUSSES FIRST ---
-            while group_people.len() > 1 && !available_minibuses.is_empty() {                   // As long as there are still people to allocate and a minibus left
-                
-                let d_idx = Self::find_willing_minibus_driver(&group_people, wants_to_drive)    // collect a willing minibus driver in the group
-                    .or_else(|| group_people.iter().position(|p| p.can_drive_minibus));
+            while group_people.len() > 1 {                                                      // As long as there are still people to allocate
+
+                // A trailer still waiting takes a minibus that can tow it, if someone here is licensed to
+                let (mb, d_idx) = match kit.towing_minibus(&group_people, &schedule, (&dest, &time), matrix, licensed) {
+                    Some((mb, d_idx)) => (mb, Some(d_idx)),
+                    None => {
+                        let Some(mb) = schedule.first_free(&dest, &time, matrix) else { break };    // ...and a minibus that isn't out at the time
+                        let d_idx = Allocation::find_willing_minibus_driver(&group_people, session, licensed) // collect a willing minibus driver in the group
+                            .or_else(|| group_people.iter().position(|p| licensed.minibus(p)));
+                        (mb, d_idx)
+                    }
+                };
 
                 if let Some(d_idx) = d_idx {                    // If we found a driver (willing or not), allocate them to the minibus and fill up with passengers
-                    let mb = available_minibuses.remove(0);
                     let driver = group_people.remove(d_idx);
-                    let cap = mb.seats as usize;
+                    schedule.book(&mb, &driver.name, &dest, &time, matrix);
                     
-                    let mut t_group = TransportGroup {          // Create a new transport group for this minibus allocation
-                        driver: driver.clone(),
-                        vehicle_label: format!("Minibus {}", mb.registration),
-                        passengers: Vec::new(),
-                        capacity: cap,
-                        pickup_location: "Pleasance".to_string(),
-                        destination: dest,
-                        departure_time: time.clone(),
-                    };
+                    let mut t_group = TransportGroup::minibus(driver, &mb, &dest, &time);  // Create a new transport group for this minibus allocation
+                    kit.reserve(std::slice::from_mut(&mut t_group));
 
                     // Fill the minibus with passengers
-                    while t_group.passengers.len() < (cap - 1) && !group_people.is_empty() {
-                        let p_idx = Self::find_best_passenger(&group_people, &t_group);
+                    while t_group.free_seats() > 0 && !group_people.is_empty() {
+                        let p_idx = Allocation::find_best_passenger(&group_people, &t_group);
                         t_group.passengers.push(group_people.remove(p_idx));
                     }
 
@@ -74,7 +177,8 @@ impl Allocation {
             // --- FILL PERSONAL CARS (AGAIN, LARGEST FIRST) ---
             while !group_people.is_empty() {
 
-                let best_driver_idx = Self::find_willing_car_driver(&group_people, wants_to_drive)  // First collect the people who actively want to drive their own car
+                let best_driver_idx = kit.towing_car(&group_people)                                    // A car that can tow a trailer still waiting comes first
+                    .or_else(|| Allocation::find_willing_car_driver(&group_people, session))       // Then the people who actively want to drive their own car
                     .or_else(|| {
                         group_people.iter().enumerate()                         // Find the driver with the largest car capacity to minimize vehicle count
                             .filter(|(_, p)| p.car.is_some())
@@ -84,21 +188,13 @@ impl Allocation {
 
                 if let Some(d_idx) = best_driver_idx {                          // If we found a driver collect them and their car
                     let driver = group_people.remove(d_idx);
-                    let car = driver.car.as_ref().unwrap().clone();
                     
-                    let mut t_group = TransportGroup {                          // Allocating the driver and car to transport sheet
-                        driver: driver.clone(),
-                        vehicle_label: format!("Car {} ({})", car.registration, car.vehicle_type),
-                        passengers: Vec::new(),
-                        capacity: car.seats as usize,
-                        pickup_location: driver.pickup_locations.first().cloned().unwrap_or("Home".to_string()),
-                        destination: dest,
-                        departure_time: time.clone(),
-                    };
+                    let mut t_group = TransportGroup::car(driver, &dest, &time);  // Allocating the driver and car to transport sheet
+                    kit.reserve(std::slice::from_mut(&mut t_group));
 
                     // Filling the car with passengers
-                    while t_group.passengers.len() < (t_group.capacity - 1) && !group_people.is_empty() {
-                        let p_idx = Self::find_best_passenger(&group_people, &t_group);
+                    while t_group.free_seats() > 0 && !group_people.is_empty() {
+                        let p_idx = Allocation::find_best_passenger(&group_people, &t_group);
                         t_group.passengers.push(group_people.remove(p_idx));
                     }
 
@@ -110,6 +206,27 @@ impl Allocation {
             }
         }
 
+        AllocationOutcome::new(Allocation::finish(final_allocations, &all_requests, minibuses, matrix, licensed))
+    }
+}
+
+// --- STEPS SHARED BY EVERY STRATEGY ---
+pub struct Allocation;
+
+impl Allocation {
+
+    // --- SHARED FINAL STEPS FOR EVERY STRATEGY: PLAN THE ROUTES THEN CHECK NOBODY WAS MISSED ---
+    pub fn finish(
+        mut final_allocations: Vec<TransportGroup>,
+        requests: &[(Destination, String, Vec<Person>)],
+        minibuses: &[Minibus],
+        matrix: &TravelMatrix,
+        licensed: &Licensed,
+    ) -> Result<Vec<TransportGroup>, AllocationReport> {
+
+        // --- ROUTE PLANNING (ORDERED PICKUP STOPS AND TIMES FOR EACH VEHICLE) ---
+        Self::plan_routes(&mut final_allocations, matrix);
+
         // --- VALIDATION ---
         let allocated_ids: HashSet<String> = final_allocations.iter()                                   // Collecting all allocated peoples IDs (passengers and drivers)
             .flat_map(|g| {
@@ -119,33 +236,39 @@ impl Allocation {
             })
             .collect();
 
-        let unallocated_names: Vec<String> = all_requested_people.into_iter()                           // Collecting any names of people who aren't allocated to anything
-            .filter(|p| !allocated_ids.contains(&p.student_id))
-            .map(|p| p.name)
-            .collect();
+        let report = AllocationReport::diagnose(&final_allocations, requests, &allocated_ids, minibuses, matrix, licensed);   // Why anyone who isn't allocated was left over
 
-        if unallocated_names.is_empty() {
+        if report.shortfalls.is_empty() {
             Ok(final_allocations)                   // If everyone is allocated, return the final transport groups
         } else {
-            Err(unallocated_names)                  // If not, return an error with the list of unallocated people
+            Err(AllocationReport { partial: final_allocations, ..report })     // If not, explain who was left over and why, keeping the partial plan
+        }
+    }
+
+    // --- ORDERED PICKUP STOPS AND ARRIVAL TIME FOR EVERY OUTBOUND VEHICLE (RE-RUN AFTER ANY CHANGE TO WHO IS IN THEM) ---
+    pub fn plan_routes(groups: &mut [TransportGroup], matrix: &TravelMatrix) {
+        for group in groups.iter_mut().filter(|g| g.leg == Leg::Outbound) {
+            let (stops, arrival) = plan_route(&group.pickup_location, &group.driver, &group.passengers, &group.destination, &group.departure_time, matrix);
+            group.stops        = stops;
+            group.arrival_time = arrival;
         }
     }
 
     // --- FIND THE BEST WILLING MINIBUS DRIVER (Wants to drive and can drive the minibus ---
-    fn find_willing_minibus_driver(pool: &[Person], wants_to_drive: &HashSet<String>) -> Option<usize> {
-        pool.iter().position(|p| p.can_drive_minibus && !wants_to_drive.contains(&p.student_id))
+    pub fn find_willing_minibus_driver(pool: &[Person], session: &Session, licensed: &Licensed) -> Option<usize> {
+        pool.iter().position(|p| licensed.minibus(p) && !session.wants_to_drive(p))
     }
 
     // --- FIND THE BEST WILLING CAR DRIVER (Must have their OWN car AND have opted in) ---
-    fn find_willing_car_driver(pool: &[Person], wants_to_drive: &HashSet<String>) -> Option<usize> {
+    pub fn find_willing_car_driver(pool: &[Person], session: &Session) -> Option<usize> {
         pool.iter().enumerate()
-            .filter(|(_, p)| p.car.is_some() && wants_to_drive.contains(&p.student_id))
+            .filter(|(_, p)| p.car.is_some() && session.wants_to_drive(p))
             .max_by_key(|(_, p)| p.car.as_ref().unwrap().seats)
             .map(|(idx, _)| idx)
     }
 
     // --- FIND THE BEST PASSENGER TO FILL A VEHICLE (Based on location match, gender balance, and time at the club) ---
-    fn find_best_passenger(pool: &[Person], group: &TransportGroup) -> usize {
+    pub fn find_best_passenger(pool: &[Person], group: &TransportGroup) -> usize {
 
         // Finding out the current gender distribution within the car being filled
         let driver_gender                   = &group.driver.gender;
@@ -169,7 +292,7 @@ impl Allocation {
             }
 
             // Middle priority - years and experience
-            score += (3000 - p.year_of_entry as i32);       // Currently 974 (as of 2026)
+            score += 3000 - p.year_of_entry as i32;       // Currently 974 (as of 2026)
 
             // Lowest priority - gender balancing
             if p.gender == preferred_gender {
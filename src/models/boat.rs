@@ -0,0 +1,116 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use super::Destination;
+
+// --- CREATING A SIMPLE ENUM FOR DIFFERENT BOAT CLASSIFICATIONS ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoatType {
+    Single,
+    Double,
+    Pair,
+    Quad,
+    Four,
+    FourCoxed,
+    EightCoxed,
+}
+
+// --- IMPLEMENTING METHODS TO RETURN SEAT COUNTS AND LABELS TO BE DISPLAYED FOR DIFFERENT BOAT TYPES ---
+impl BoatType {
+    pub fn seat_count(&self) -> usize {
+        match self {
+            BoatType::Single => 1,
+            BoatType::Double | BoatType::Pair => 2,
+            BoatType::Quad | BoatType::Four => 4,
+            BoatType::FourCoxed => 5,
+            BoatType::EightCoxed => 9,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BoatType::Single => "1x",
+            BoatType::Double => "2x",
+            BoatType::Pair => "2-",
+            BoatType::Quad => "4x-",
+            BoatType::Four => "4-",
+            BoatType::FourCoxed => "4+",
+            BoatType::EightCoxed => "8+",
+        }
+    }
+
+    pub fn has_cox(&self) -> bool {
+        matches!(self, BoatType::FourCoxed | BoatType::EightCoxed)
+    }
+
+    // --- WHAT A SEAT IS CALLED: BOW FIRST, THEN NUMBERS UP TO STROKE, WITH THE COX LAST IN COXED BOATS ---
+    pub fn seat_label(&self, s_idx: usize) -> String {
+        let last = self.seat_count() - 1;
+        if self.has_cox() && s_idx == last {
+            "COX".to_string()
+        } else if s_idx == 0 {
+            "BOW".to_string()
+        } else if (self.has_cox() && s_idx == last - 1) || s_idx == last {
+            "STR".to_string()                                   // Stroke is second last if coxed, otherwise last
+        } else {
+            (s_idx + 1).to_string()
+        }
+    }
+}
+
+// --- DEFINING A STRUCTURE TO REPRESENT EACH BOAT ON THE LAKE (AND HANDLE UI STUFF (pos)) ---
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Boat {
+    pub boat_type: BoatType,
+    pub seats: Vec<Option<String>>,         // Each seat can be None (empty) or Some(student_id)
+    #[serde(default = "Boat::default_pos")]
+    pub pos: (f32, f32),                    // Position of the "boat" on the frame (x, y) - not needed in hand written crew files
+    pub departure_time: String,             // Departure time (e.g. "07:00")
+    pub destination: Option<Destination>,   // Venue for specific boat (e.g. auchenstarry or strathclyde)
+    #[serde(default)]
+    pub return_time: String,                // When the crew is ready to leave the venue (blank uses the venue's usual session length)
+    #[serde(default)]
+    pub drop_off: Option<String>,           // Pickup point the crew wants dropping at on the way back (None = where they got in)
+    #[serde(default)]
+    pub own_transport: HashSet<String>,     // Student IDs of the crew making their own way (dropped when they leave their seat)
+}
+
+impl Boat {
+
+    // --- CREATES AN EMPTY BOAT OF THE GIVEN TYPE IN THE MIDDLE OF THE FRAME ---
+    pub fn new(boat_type: BoatType) -> Self {
+        Boat {
+            boat_type,
+            seats: vec![None; boat_type.seat_count()],
+            pos: Self::default_pos(),
+            departure_time: String::new(),
+            destination: None,
+            return_time: String::new(),
+            drop_off: None,
+            own_transport: HashSet::new(),
+        }
+    }
+
+    pub fn is_own_transport(&self, student_id: &str) -> bool {
+        self.own_transport.contains(student_id)
+    }
+
+    // --- MARKS WHOEVER SITS IN A SEAT AS MAKING THEIR OWN WAY (AND BACK AGAIN) - AN EMPTY SEAT HAS NOBODY TO MARK ---
+    pub fn toggle_own_transport(&mut self, s_idx: usize) {
+        let Some(Some(id)) = self.seats.get(s_idx) else { return };
+        if !self.own_transport.remove(id) {
+            self.own_transport.insert(id.clone());
+        }
+    }
+
+    // --- PUTS SOMEONE IN A SEAT (OR EMPTIES IT) - WHOEVER SAT THERE BEFORE NO LONGER COUNTS AS MAKING THEIR OWN WAY ---
+    pub fn set_seat(&mut self, s_idx: usize, student_id: Option<String>) {
+        let Some(seat) = self.seats.get_mut(s_idx) else { return };
+        if let Some(old) = std::mem::replace(seat, student_id) {
+            self.own_transport.remove(&old);
+        }
+    }
+
+    fn default_pos() -> (f32, f32) {
+        (400.0, 300.0)
+    }
+}
@@ -1,8 +1,11 @@
 use serde::{Serialize, Deserialize};
+use super::equipment::Haulage;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Car {
     pub vehicle_type: String,
     pub registration: String,
     pub seats: u8,
-}
\ No newline at end of file
+    #[serde(flatten)]
+    pub kit: Haulage,                       // Tow bar and boot space
+}
@@ -0,0 +1,280 @@
+use std::collections::HashSet;
+use serde::{Serialize, Deserialize};
+use super::{Person, Session, Destination, TransportGroup, Leg};
+use super::edit::{find_person, make_driver, check_can_drive};
+use super::credentials::DriverCredentials;
+use chrono::NaiveDate;
+
+// --- A RULE FOR ONE SESSION THAT THE ALLOCATOR MUST HONOUR (PEOPLE ARE STUDENT IDS) ---
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Constraint {
+    Together(Vec<String>),                  // Ride in the same vehicle (e.g. siblings, or a coach with a junior)
+    Apart(Vec<String>),                     // Never two of them in the same vehicle
+    MustDrive(String),
+    MustNotDrive(String),
+    TravelsPrivately(String),               // Makes their own way, so isn't given a seat at all
+}
+
+// --- THE KINDS OF CONSTRAINT, FOR PICKING ONE IN THE APP ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConstraintKind {
+    #[default]
+    Together,
+    Apart,
+    MustDrive,
+    MustNotDrive,
+    TravelsPrivately,
+}
+
+impl ConstraintKind {
+    pub const ALL: [ConstraintKind; 5] = [
+        ConstraintKind::Together,
+        ConstraintKind::Apart,
+        ConstraintKind::MustDrive,
+        ConstraintKind::MustNotDrive,
+        ConstraintKind::TravelsPrivately,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConstraintKind::Together => "Ride together",
+            ConstraintKind::Apart => "Never together",
+            ConstraintKind::MustDrive => "Must drive",
+            ConstraintKind::MustNotDrive => "Not driving",
+            ConstraintKind::TravelsPrivately => "Travels privately",
+        }
+    }
+
+    // --- BUILDS THE CONSTRAINT FROM THE PEOPLE PICKED (TWO OR MORE FOR TOGETHER / APART, OTHERWISE EXACTLY ONE) ---
+    pub fn build(&self, mut ids: Vec<String>) -> Result<Constraint, String> {
+        match self {
+            ConstraintKind::Together | ConstraintKind::Apart if ids.len() < 2 => Err(format!("\"{}\" needs at least two people.", self.label())),
+            ConstraintKind::Together => Ok(Constraint::Together(ids)),
+            ConstraintKind::Apart => Ok(Constraint::Apart(ids)),
+            _ if ids.len() != 1 => Err(format!("\"{}\" is for one person at a time.", self.label())),
+            ConstraintKind::MustDrive => Ok(Constraint::MustDrive(ids.remove(0))),
+            ConstraintKind::MustNotDrive => Ok(Constraint::MustNotDrive(ids.remove(0))),
+            ConstraintKind::TravelsPrivately => Ok(Constraint::TravelsPrivately(ids.remove(0))),
+        }
+    }
+}
+
+impl Constraint {
+    pub fn kind(&self) -> ConstraintKind {
+        match self {
+            Constraint::Together(_) => ConstraintKind::Together,
+            Constraint::Apart(_) => ConstraintKind::Apart,
+            Constraint::MustDrive(_) => ConstraintKind::MustDrive,
+            Constraint::MustNotDrive(_) => ConstraintKind::MustNotDrive,
+            Constraint::TravelsPrivately(_) => ConstraintKind::TravelsPrivately,
+        }
+    }
+
+    pub fn people(&self) -> &[String] {
+        match self {
+            Constraint::Together(ids) | Constraint::Apart(ids) => ids,
+            Constraint::MustDrive(id) | Constraint::MustNotDrive(id) | Constraint::TravelsPrivately(id) => std::slice::from_ref(id),
+        }
+    }
+}
+
+// --- APPLIES THE RULES THAT CAN BE SETTLED BEFORE ALLOCATING, FOR EVERY STRATEGY ALIKE ---
+// People travelling privately are taken out of the requests, anyone not driving loses their licence and car for the
+// day (as does anyone whose credentials don't cover their car on the day `on`), and car owners who must drive (or support staff
+// who'd rather drive) are opted in. Returns the requests and a copy of the session to allocate with.
+pub fn prepare(requests: Vec<(Destination, String, Vec<Person>)>, session: &Session, on: NaiveDate) -> (Vec<(Destination, String, Vec<Person>)>, Session) {
+    let mut session = session.clone();
+    let mut private: HashSet<&str>   = HashSet::new();
+    let mut no_driving: HashSet<&str> = HashSet::new();
+    for c in &session.constraints {
+        match c {
+            Constraint::TravelsPrivately(id) => { private.insert(id); }
+            Constraint::MustNotDrive(id) => { no_driving.insert(id); }
+            _ => {}
+        }
+    }
+
+    let must_drive: HashSet<&str> = session.constraints.iter()
+        .filter_map(|c| if let Constraint::MustDrive(id) = c { Some(id.as_str()) } else { None })
+        .collect();
+
+    // Support staff who prefer driving bring their car unless they could take a minibus instead
+    let preferred: HashSet<&str> = session.support.iter()
+        .filter(|s| s.prefers_driving)
+        .map(|s| s.student_id.as_str())
+        .collect();
+    let car_drivers: Vec<String> = requests.iter()
+        .flat_map(|(_, _, people)| people)
+        .filter(|p| p.can_drive_car(on) && !no_driving.contains(p.student_id.as_str()))
+        .filter(|p| must_drive.contains(p.student_id.as_str()) || (preferred.contains(p.student_id.as_str()) && !p.can_drive_minibus(on)))
+        .map(|p| p.student_id.clone())
+        .collect();
+
+    let requests = requests.into_iter().map(|(dest, time, people)| {
+        let people = people.into_iter()
+            .filter(|p| !private.contains(p.student_id.as_str()))
+            .map(|mut p| {
+                if no_driving.contains(p.student_id.as_str()) {
+                    p.credentials = DriverCredentials::default();
+                    p.car         = None;
+                } else if !p.can_drive_car(on) {
+                    p.car = None;
+                }
+                p
+            })
+            .collect();
+        (dest, time, people)
+    }).collect();
+
+    // Minibus drivers are picked from people not driving their own car, so only car owners are opted in here
+    for id in car_drivers {
+        session.driver_overrides.insert(id, true);
+    }
+    (requests, session)
+}
+
+// --- MOVES PEOPLE AROUND AFTER AN ALLOCATION UNTIL THE REMAINING RULES HOLD, AS FAR AS SWAPS ALLOW ---
+// Only people not named in any constraint are swapped out of the way, and vehicles never go over capacity
+pub fn apply_constraints(groups: &mut Vec<TransportGroup>, constraints: &[Constraint], on: NaiveDate) {
+    let pinned: HashSet<String> = constraints.iter()
+        .filter(|c| !matches!(c, Constraint::MustNotDrive(_) | Constraint::TravelsPrivately(_)))
+        .flat_map(|c| c.people().iter().cloned())
+        .collect();
+
+    // Drivers first, since a car brought out for someone who must drive gives the others more room to move
+    for c in constraints {
+        if let Constraint::MustDrive(id) = c { put_at_wheel(groups, id, on); }
+    }
+    for c in constraints {
+        match c {
+            Constraint::Together(ids) => keep_together(groups, ids, &pinned),
+            Constraint::Apart(ids) => keep_apart(groups, ids, &pinned),
+            _ => {}                         // Drivers done above; not driving and travelling privately are settled before allocating
+        }
+    }
+}
+
+// --- ONE LINE FOR EACH CONSTRAINT THE PLAN BREAKS (PEOPLE NOT TRAVELLING THIS TIME ARE IGNORED) ---
+pub fn violations(groups: &[TransportGroup], constraints: &[Constraint]) -> Vec<String> {
+    let name_of = |id: &str| find_person(groups, id).map(|(g_idx, role)| match role {
+        Some(p_idx) => groups[g_idx].passengers[p_idx].name.clone(),
+        None => groups[g_idx].driver.name.clone(),
+    });
+
+    let mut broken = Vec::new();
+    for c in constraints {
+        match c {
+            Constraint::Together(ids) => {
+                let found: Vec<(usize, String)> = ids.iter().filter_map(|id| Some((find_person(groups, id)?.0, name_of(id)?))).collect();
+                let vehicles: HashSet<usize> = found.iter().map(|(g_idx, _)| *g_idx).collect();
+                if vehicles.len() > 1 {
+                    let names: Vec<&str> = found.iter().map(|(_, n)| n.as_str()).collect();
+                    broken.push(format!("Constraint broken: {} should ride together but are split across {} vehicles.", names.join(", "), vehicles.len()));
+                }
+            }
+            Constraint::Apart(ids) => {
+                for (g_idx, group) in groups.iter().enumerate().filter(|(_, g)| g.leg == Leg::Outbound) {
+                    let names: Vec<String> = ids.iter()
+                        .filter(|id| find_person(groups, id).is_some_and(|(g, _)| g == g_idx))
+                        .filter_map(|id| name_of(id))
+                        .collect();
+                    if names.len() > 1 {
+                        broken.push(format!("Constraint broken: {} should never share a vehicle but are all in {}.", names.join(", "), group.vehicle_label));
+                    }
+                }
+            }
+            Constraint::MustDrive(id) => {
+                if let Some((g_idx, Some(_))) = find_person(groups, id) {
+                    broken.push(format!("Constraint broken: {} must drive today but is a passenger in {}.", name_of(id).unwrap_or_default(), groups[g_idx].vehicle_label));
+                }
+            }
+            Constraint::MustNotDrive(id) => {
+                if let Some((g_idx, None)) = find_person(groups, id) {
+                    broken.push(format!("Constraint broken: {} is not driving today but drives {}.", groups[g_idx].driver.name, groups[g_idx].vehicle_label));
+                }
+            }
+            Constraint::TravelsPrivately(id) => {
+                if let Some((g_idx, _)) = find_person(groups, id) {
+                    broken.push(format!("Constraint broken: {} travels privately but is in {}.", name_of(id).unwrap_or_default(), groups[g_idx].vehicle_label));
+                }
+            }
+        }
+    }
+    broken
+}
+
+fn same_departure(a: &TransportGroup, b: &TransportGroup) -> bool {
+    a.destination == b.destination && a.departure_time == b.departure_time
+}
+
+// --- PUTS A PASSENGER IN ANOTHER VEHICLE, SWAPPING WITH AN UNPINNED PASSENGER THERE IF IT'S FULL ---
+fn move_or_swap(groups: &mut [TransportGroup], from: usize, p_idx: usize, to: usize, pinned: &HashSet<String>) -> bool {
+    if groups[to].free_seats() > 0 {
+        let person = groups[from].passengers.remove(p_idx);
+        groups[to].passengers.push(person);
+        return true;
+    }
+    let Some(q_idx) = groups[to].passengers.iter().position(|q| !pinned.contains(&q.student_id)) else { return false };
+    let person    = groups[from].passengers.remove(p_idx);
+    let displaced = std::mem::replace(&mut groups[to].passengers[q_idx], person);
+    groups[from].passengers.insert(p_idx, displaced);
+    true
+}
+
+// --- GATHERS THE GROUP INTO WHICHEVER VEHICLE ALREADY HOLDS MOST OF THEM (PER DEPARTURE) ---
+fn keep_together(groups: &mut [TransportGroup], ids: &[String], pinned: &HashSet<String>) {
+    let found: Vec<usize> = ids.iter().filter_map(|id| find_person(groups, id)).map(|(g_idx, _)| g_idx).collect();
+    let Some(target) = found.iter().copied().max_by_key(|&g| (found.iter().filter(|&&h| h == g).count(), groups[g].free_seats())) else { return };
+
+    for id in ids {
+        let Some((from, Some(p_idx))) = find_person(groups, id) else { continue };     // Drivers stay with their own vehicle
+        if from != target && same_departure(&groups[from], &groups[target]) {
+            move_or_swap(groups, from, p_idx, target, pinned);
+        }
+    }
+}
+
+// --- SPREADS THE GROUP OUT SO NO VEHICLE HOLDS TWO OF THEM ---
+fn keep_apart(groups: &mut [TransportGroup], ids: &[String], pinned: &HashSet<String>) {
+    for id in ids {
+        let Some((from, Some(p_idx))) = find_person(groups, id) else { continue };
+        let sharing = ids.iter().filter(|other| *other != id && find_person(groups, other).is_some_and(|(g, _)| g == from)).count();
+        if sharing == 0 { continue; }
+
+        let clear = (0..groups.len()).find(|&to| {
+            to != from
+                && groups[to].leg == Leg::Outbound
+                && same_departure(&groups[from], &groups[to])
+                && !ids.iter().any(|other| find_person(groups, other).is_some_and(|(g, _)| g == to))
+                && (groups[to].free_seats() > 0 || groups[to].passengers.iter().any(|q| !pinned.contains(&q.student_id)))
+        });
+        if let Some(to) = clear {
+            move_or_swap(groups, from, p_idx, to, pinned);
+        }
+    }
+}
+
+// --- GETS SOMEONE BEHIND A WHEEL: THEIR OWN VEHICLE, A MINIBUS ON THEIR DEPARTURE, OR THEIR OWN CAR ---
+fn put_at_wheel(groups: &mut Vec<TransportGroup>, id: &str, on: NaiveDate) {
+    let Some((from, Some(p_idx))) = find_person(groups, id) else { return };
+    if make_driver(groups, from, p_idx, on).is_ok() { return; }
+
+    let person = &groups[from].passengers[p_idx];
+    let minibus = (0..groups.len()).find(|&to| {
+        to != from && groups[to].leg == Leg::Outbound && groups[to].is_minibus()
+            && same_departure(&groups[from], &groups[to])
+            && check_can_drive(person, &groups[to], groups[to].passengers.len(), on).is_ok()
+    });
+    if let Some(to) = minibus {
+        let person     = groups[from].passengers.remove(p_idx);
+        let old_driver = std::mem::replace(&mut groups[to].driver, person);
+        groups[from].passengers.insert(p_idx, old_driver);
+        return;
+    }
+
+    if person.car.is_some() {
+        let owner = groups[from].passengers.remove(p_idx);
+        let car   = TransportGroup::car(owner, &groups[from].destination, &groups[from].departure_time);
+        groups.insert(from + 1, car);
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashSet;
+use chrono::{NaiveDate, Months, Local};
+use serde::{Serialize, Deserialize};
+use super::{Person, Destination};
+
+// --- DRIVING LICENCE CATEGORIES THAT MATTER FOR CLUB TRANSPORT ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LicenceCategory {
+    B,                                      // Cars
+    BE,                                     // Car towing a trailer
+    D1,                                     // Minibuses
+    D1E,                                    // Minibus towing a trailer
+}
+
+impl LicenceCategory {
+    pub const ALL: [LicenceCategory; 4] = [LicenceCategory::B, LicenceCategory::BE, LicenceCategory::D1, LicenceCategory::D1E];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LicenceCategory::B => "B",
+            LicenceCategory::BE => "B+E",
+            LicenceCategory::D1 => "D1",
+            LicenceCategory::D1E => "D1+E",
+        }
+    }
+}
+
+// --- WHAT A PERSON IS ALLOWED TO DRIVE, AND UNTIL WHEN ---
+// Dates are written YYYY-MM-DD and left blank when not known. Blank dates aren't enforced (they're warned about
+// instead), except that driving a minibus on a B licence needs the licence date, date of birth and assessment to prove it's allowed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DriverCredentials {
+    #[serde(default)]
+    pub categories: Vec<LicenceCategory>,
+    #[serde(default)]
+    pub licence_since: String,              // When the full licence was first held
+    #[serde(default)]
+    pub date_of_birth: String,
+    #[serde(default)]
+    pub assessment_date: String,            // University minibus driver assessment (valid for ASSESSMENT_VALID_YEARS)
+    #[serde(default)]
+    pub insurance_expiry: String,           // Club insurance cover for this driver
+}
+
+impl DriverCredentials {
+    pub const MINIBUS_MIN_AGE: u32          = 21;
+    pub const B_LICENCE_MINIBUS_YEARS: u32  = 2;    // Years a B licence must be held to drive a minibus without D1
+    pub const ASSESSMENT_VALID_YEARS: u32   = 4;
+    pub const LAPSE_WARNING_DAYS: i64       = 30;   // How far ahead the app warns about an assessment or insurance running out
+
+    pub fn has(&self, category: LicenceCategory) -> bool {
+        self.categories.contains(&category)
+    }
+
+    pub fn toggle(&mut self, category: LicenceCategory) {
+        match self.categories.iter().position(|c| *c == category) {
+            Some(idx) => { self.categories.remove(idx); }
+            None => self.categories.push(category),
+        }
+    }
+
+    fn date(text: &str) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").ok()
+    }
+
+    // Whole years from `since` to `on`
+    fn years_between(since: NaiveDate, on: NaiveDate) -> u32 {
+        on.years_since(since).unwrap_or(0)
+    }
+
+    pub fn assessment_expiry(&self) -> Option<NaiveDate> {
+        Self::date(&self.assessment_date)?.checked_add_months(Months::new(12 * Self::ASSESSMENT_VALID_YEARS))
+    }
+
+    // --- WHY THEY CAN'T DRIVE A MINIBUS ON THIS DAY (NONE IF THEY CAN) ---
+    pub fn minibus_refusal(&self, on: NaiveDate) -> Option<String> {
+        let age = Self::date(&self.date_of_birth).map(|dob| Self::years_between(dob, on));
+        if !self.has(LicenceCategory::D1) {
+            let held = Self::date(&self.licence_since).map(|since| Self::years_between(since, on));
+            match (self.has(LicenceCategory::B), held, age) {
+                (false, _, _) => return Some("no D1 or B licence".to_string()),
+                (true, None, _) | (true, _, None) => return Some("licence date or date of birth not recorded for a B licence".to_string()),
+                (true, Some(years), _) if years < Self::B_LICENCE_MINIBUS_YEARS => {
+                    return Some(format!("B licence held under {} years", Self::B_LICENCE_MINIBUS_YEARS));
+                }
+                _ => {}
+            }
+            // Without D1 the university assessment is what qualifies them
+            if self.assessment_date.trim().is_empty() {
+                return Some("no minibus assessment recorded".to_string());
+            }
+        }
+        if let Some(age) = age && age < Self::MINIBUS_MIN_AGE {
+            return Some(format!("under {}", Self::MINIBUS_MIN_AGE));
+        }
+        if let Some(expiry) = self.assessment_expiry() && expiry < on {
+            return Some(format!("minibus assessment lapsed on {}", expiry));
+        }
+        self.insurance_refusal(on)
+    }
+
+    // --- WHY THEY CAN'T DRIVE THEIR OWN CAR FOR THE CLUB ON THIS DAY (NONE IF THEY CAN) ---
+    pub fn car_refusal(&self, on: NaiveDate) -> Option<String> {
+        if !self.has(LicenceCategory::B) {
+            return Some("no B licence".to_string());
+        }
+        self.insurance_refusal(on)
+    }
+
+    fn insurance_refusal(&self, on: NaiveDate) -> Option<String> {
+        match Self::date(&self.insurance_expiry) {
+            Some(expiry) if expiry < on => Some(format!("insurance expired on {}", expiry)),
+            _ => None,
+        }
+    }
+
+    // Someone counts as a minibus driver if they hold D1 or have been through the assessment
+    pub fn claims_minibus(&self) -> bool {
+        self.has(LicenceCategory::D1) || !self.assessment_date.trim().is_empty()
+    }
+
+    // --- TOWING NEEDS THE +E CATEGORY FOR THE VEHICLE BEING DRIVEN ---
+    pub fn can_tow(&self, minibus: bool) -> bool {
+        if minibus { self.has(LicenceCategory::D1E) } else { self.has(LicenceCategory::BE) }
+    }
+
+    // --- ANYTHING EXPIRED, ABOUT TO LAPSE OR NOT RECORDED, FOR SHOWING NEXT TO A DRIVER IN THE APP ---
+    pub fn warnings(&self, on: NaiveDate, drives_minibus: bool, drives_car: bool) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let soon = |date: NaiveDate| (date - on).num_days() <= Self::LAPSE_WARNING_DAYS;
+
+        if drives_minibus {
+            match self.assessment_expiry() {
+                Some(expiry) if expiry < on => warnings.push(format!("Minibus assessment lapsed on {}", expiry)),
+                Some(expiry) if soon(expiry) => warnings.push(format!("Minibus assessment lapses on {}", expiry)),
+                None => warnings.push("No minibus assessment date recorded".to_string()),
+                _ => {}
+            }
+        }
+        if drives_minibus || drives_car {
+            match Self::date(&self.insurance_expiry) {
+                Some(expiry) if expiry < on => warnings.push(format!("Insurance expired on {}", expiry)),
+                Some(expiry) if soon(expiry) => warnings.push(format!("Insurance expires on {}", expiry)),
+                None => warnings.push("No insurance expiry recorded".to_string()),
+                _ => {}
+            }
+        }
+        warnings
+    }
+
+    // --- CHECKS EVERY DATE GIVEN CAN BE READ ---
+    pub fn validate(&self) -> Result<(), String> {
+        let dates = [
+            ("Licence since", &self.licence_since),
+            ("Date of birth", &self.date_of_birth),
+            ("Assessment date", &self.assessment_date),
+            ("Insurance expiry", &self.insurance_expiry),
+        ];
+        for (label, text) in dates {
+            if !text.trim().is_empty() && Self::date(text).is_none() {
+                return Err(format!("{} '{}' must be written as YYYY-MM-DD (e.g. 2025-09-01).", label, text));
+            }
+        }
+        Ok(())
+    }
+}
+
+// --- WHO MAY DRIVE A MINIBUS ON THE DAY OF THE OUTING, BY STUDENT ID ---
+// Worked out once before allocating, because the strategies ask about every combination of cars they try.
+// Cars need no such list: anyone who can't drive theirs on the day has it taken off them before allocating.
+#[derive(Debug, Clone, Default)]
+pub struct Licensed {
+    minibus: HashSet<String>,
+}
+
+impl Licensed {
+    pub fn on<'a>(people: impl IntoIterator<Item = &'a Person>, date: NaiveDate) -> Self {
+        Licensed {
+            minibus: people.into_iter().filter(|p| p.can_drive_minibus(date)).map(|p| p.student_id.clone()).collect(),
+        }
+    }
+
+    pub fn minibus(&self, person: &Person) -> bool {
+        self.minibus.contains(&person.student_id)
+    }
+}
+
+// --- NOTES ON ANYONE TRAVELLING WHO WOULD NORMALLY DRIVE BUT ISN'T ALLOWED TO ON THIS DAY ---
+// Minibus drivers are judged by `claims_minibus`, and car owners only count if they've opted in to driving
+pub fn refusals(requests: &[(Destination, String, Vec<Person>)], on: NaiveDate) -> Vec<String> {
+    let mut notes = Vec::new();
+    for p in requests.iter().flat_map(|(_, _, people)| people) {
+        let creds = &p.credentials;
+        if creds.claims_minibus()
+            && let Some(reason) = creds.minibus_refusal(on)
+        {
+            notes.push(format!("Not driving a minibus today: {} ({}).", p.name, reason));
+        }
+        if let Some(car) = &p.car
+            && p.wants_to_drive
+            && let Some(reason) = creds.car_refusal(on)
+        {
+            notes.push(format!("Not driving their {} today: {} ({}).", car.vehicle_type, p.name, reason));
+        }
+    }
+    notes
+}
+
+// --- TURNS THE OLD `can_drive_minibus` / `licensed_to_tow` FLAGS ON A SAVED PERSON INTO LICENCE CATEGORIES ---
+// Car owners are taken to hold B. Dates can't be recovered, so they're left blank and show up as warnings in the app.
+pub fn upgrade_legacy_flags(person: &mut serde_json::Value) {
+    let Some(fields) = person.as_object_mut() else { return };
+    if fields.contains_key("credentials") { return; }
+
+    let flag = |fields: &mut serde_json::Map<String, serde_json::Value>, key: &str| {
+        fields.remove(key).and_then(|v| v.as_bool()).unwrap_or(false)
+    };
+    let minibus = flag(fields, "can_drive_minibus");
+    let tows    = flag(fields, "licensed_to_tow");
+    let has_car = fields.get("car").is_some_and(|c| !c.is_null());
+
+    let mut creds = DriverCredentials::default();
+    if minibus || has_car || tows { creds.categories.push(LicenceCategory::B); }
+    if tows                       { creds.categories.push(LicenceCategory::BE); }
+    if minibus                    { creds.categories.push(LicenceCategory::D1); }
+    if minibus && tows            { creds.categories.push(LicenceCategory::D1E); }
+
+    if let Ok(value) = serde_json::to_value(creds) {
+        fields.insert("credentials".to_string(), value);
+    }
+}
+
+// --- THE DAY DRIVERS ARE CHECKED AGAINST WHEN A SESSION HAS NO OUTING DATE ---
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
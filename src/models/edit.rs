@@ -0,0 +1,291 @@
+use std::collections::HashSet;
+use super::{Person, Session, Lock, SupportRole, TravelMatrix, TransportGroup, Leg, Allocation, AllocationReport};
+use super::report::Shortfall;
+use super::returns::plan_journeys_home;
+use super::constraints::violations;
+use super::equipment::{check_can_carry, equipment_problems};
+use chrono::NaiveDate;
+use super::venue::venue_label;
+
+// --- AN ALLOCATION THAT CAN BE CHANGED BY HAND BEFORE THE TRANSPORT SHEET IS PRINTED ---
+// Only the outbound vehicles are edited - the journeys home are planned again from them after every change
+#[derive(Debug, Clone, Default)]
+pub struct TransportPlan {
+    pub groups: Vec<TransportGroup>,        // Outbound vehicles first, then the journeys home
+    pub needs_transport: Vec<Shortfall>,    // Anyone the allocator couldn't seat (and nobody has placed by hand yet)
+    pub notes: Vec<String>,                 // The allocator's notes, then stranded people and clashes after each change
+    pub making_own_way: Vec<OwnWay>,        // Crew members travelling privately, printed so drivers don't wait for them
+    pub support_staff: Vec<SupportTrip>,    // Coaches, launch drivers, spares and cox trainees, listed apart from the crews
+}
+
+// --- SOMEONE IN A CREW WHO IS MAKING THEIR OWN WAY, LISTED ON THE SHEET BUT GIVEN NO SEAT ---
+#[derive(Debug, Clone)]
+pub struct OwnWay {
+    pub person: Person,
+    pub departure_time: String,
+    pub venue_name: String,
+}
+
+// --- SOMEONE FROM THE SUPPORT STAFF TRAY, LISTED ON THE SHEET WITH THEIR ROLE ---
+#[derive(Debug, Clone)]
+pub struct SupportTrip {
+    pub person: Person,
+    pub role: SupportRole,
+    pub departure_time: String,
+    pub venue_name: String,
+}
+
+impl TransportPlan {
+
+    // --- BUILDS THE PLAN FROM AN ALLOCATION RESULT, KEEPING A PARTIAL PLAN IF NOT EVERYONE FITTED ---
+    pub fn from_result(result: Result<Vec<TransportGroup>, AllocationReport>, notes: Vec<String>, making_own_way: Vec<OwnWay>) -> Self {
+        match result {
+            Ok(groups) => TransportPlan { groups, needs_transport: Vec::new(), notes, making_own_way, support_staff: Vec::new() },
+            Err(report) => TransportPlan { groups: report.partial, needs_transport: report.shortfalls, notes, making_own_way, support_staff: Vec::new() },
+        }
+    }
+
+    // --- THE OUTBOUND VEHICLE SOMEONE IS IN, IF THEY HAVE ONE ---
+    pub fn outbound_vehicle(&self, student_id: &str) -> Option<&TransportGroup> {
+        self.groups.iter()
+            .filter(|g| g.leg == Leg::Outbound)
+            .find(|g| g.driver.student_id == student_id || g.passengers.iter().any(|p| p.student_id == student_id))
+    }
+
+    // --- THE SAME TEXT AS THE ALLOCATION ERROR, FOR ANYONE STILL WITHOUT A SEAT ---
+    pub fn needs_transport_report(&self) -> Option<String> {
+        if self.needs_transport.is_empty() { return None; }
+        Some(AllocationReport { shortfalls: self.needs_transport.clone(), partial: Vec::new() }.to_string())
+    }
+
+    // --- MOVES A PASSENGER INTO ANOTHER VEHICLE ON THE SAME DEPARTURE ---
+    pub fn move_passenger(&mut self, from: usize, p_idx: usize, to: usize, session: &Session, matrix: &TravelMatrix) -> Result<(), String> {
+        move_passenger(&mut self.groups, from, p_idx, to, matrix)?;
+        self.replan(session, matrix);
+        Ok(())
+    }
+
+    // --- HANDS THE WHEEL TO ONE OF A VEHICLE'S PASSENGERS ---
+    pub fn make_driver(&mut self, g_idx: usize, p_idx: usize, session: &Session, matrix: &TravelMatrix) -> Result<(), String> {
+        make_driver(&mut self.groups, g_idx, p_idx, session.outing_date())?;
+        self.replan(session, matrix);
+        Ok(())
+    }
+
+    // --- GIVES SOMEONE FROM "NEEDS TRANSPORT" A SEAT IN A VEHICLE ON THEIR DEPARTURE ---
+    pub fn seat_stranded(&mut self, s_idx: usize, p_idx: usize, to: usize, session: &Session, matrix: &TravelMatrix) -> Result<(), String> {
+        let shortfall = &self.needs_transport[s_idx];
+        let target    = &self.groups[to];
+        let person    = &shortfall.people[p_idx];
+
+        if target.destination != shortfall.destination || target.departure_time != shortfall.departure_time {
+            return Err(format!(
+                "{} needs the {} {} departure, not {} {}.",
+                person.name, shortfall.departure_time, shortfall.venue_name, target.departure_time, venue_label(matrix.venues, &target.destination),
+            ));
+        }
+        if target.free_seats() == 0 {
+            return Err(format!("{} is full ({} seats).", target.vehicle_label, target.capacity));
+        }
+
+        let person = self.needs_transport[s_idx].people.remove(p_idx);
+        self.groups[to].passengers.push(person);
+        self.needs_transport.retain(|s| !s.people.is_empty());
+        self.replan(session, matrix);
+        Ok(())
+    }
+
+    // --- PLANS THE ROUTES AND JOURNEYS HOME AGAIN AFTER A CHANGE ---
+    pub fn replan(&mut self, session: &Session, matrix: &TravelMatrix) {
+        self.groups.retain(|g| g.leg == Leg::Outbound);
+        Allocation::plan_routes(&mut self.groups, matrix);
+        self.notes = plan_journeys_home(&mut self.groups, session, matrix);
+        self.notes.extend(violations(&self.groups, &session.constraints));
+        self.notes.extend(equipment_problems(&self.groups, &session.equipment, matrix));
+    }
+
+    // --- POINTS EVERY LOCK AT WHEREVER ITS PERSON NOW IS, SO A LOCKED PERSON MOVED BY HAND STAYS LOCKED THERE ---
+    pub fn sync_locks(&self, locks: &mut [Lock]) {
+        for lock in locks {
+            if let Some((g_idx, role)) = find_person(&self.groups, &lock.student_id) {
+                lock.registration = self.groups[g_idx].registration.clone();
+                lock.driving      = role.is_none();
+            }
+        }
+    }
+}
+
+// --- WHERE A PERSON IS IN THE OUTBOUND VEHICLES: (GROUP INDEX, PASSENGER INDEX OR NONE FOR THE DRIVER) ---
+pub fn find_person(groups: &[TransportGroup], student_id: &str) -> Option<(usize, Option<usize>)> {
+    groups.iter().enumerate().filter(|(_, g)| g.leg == Leg::Outbound).find_map(|(g_idx, g)| {
+        if g.driver.student_id == student_id {
+            return Some((g_idx, None));
+        }
+        g.passengers.iter().position(|p| p.student_id == student_id).map(|p_idx| (g_idx, Some(p_idx)))
+    })
+}
+
+fn same_departure(a: &TransportGroup, b: &TransportGroup) -> bool {
+    a.destination == b.destination && a.departure_time == b.departure_time
+}
+
+// --- CHECKS A PERSON CAN DRIVE A VEHICLE CARRYING `passengers` PEOPLE ON THE DAY `on` (A CAR IS ALWAYS ITS DRIVER'S OWN) ---
+pub fn check_can_drive(person: &Person, group: &TransportGroup, passengers: usize, on: NaiveDate) -> Result<(), String> {
+    if group.is_minibus() {
+        if let Some(reason) = person.credentials.minibus_refusal(on) {
+            return Err(format!("{} can't drive a minibus ({}).", person.name, reason));
+        }
+        return check_can_carry(person, group);
+    }
+    if let Some(car) = &person.car
+        && let Some(reason) = person.credentials.car_refusal(on)
+    {
+        return Err(format!("{} can't drive their {} ({}).", person.name, car.vehicle_type, reason));
+    }
+    match &person.car {
+        None => Err(format!("{} has no car to drive.", person.name)),
+        Some(car) if (car.seats as usize) < passengers + 1 => Err(format!(
+            "{}'s {} only has {} seats, and {} people would be in it.", person.name, car.vehicle_type, car.seats, passengers + 1,
+        )),
+        Some(_) => check_can_carry(person, group),
+    }
+}
+
+// --- MOVES A PASSENGER BETWEEN TWO OUTBOUND VEHICLES ON THE SAME DEPARTURE, IF THERE IS ROOM ---
+pub fn move_passenger(groups: &mut [TransportGroup], from: usize, p_idx: usize, to: usize, matrix: &TravelMatrix) -> Result<(), String> {
+    if from == to { return Ok(()); }
+
+    let (source, target) = (&groups[from], &groups[to]);
+    if !same_departure(source, target) {
+        return Err(format!(
+            "{} is on the {} {} departure, not {} {}.",
+            source.passengers[p_idx].name,
+            source.departure_time, venue_label(matrix.venues, &source.destination),
+            target.departure_time, venue_label(matrix.venues, &target.destination),
+        ));
+    }
+    if target.free_seats() == 0 {
+        return Err(format!("{} is full ({} seats).", target.vehicle_label, target.capacity));
+    }
+
+    let person = groups[from].passengers.remove(p_idx);
+    groups[to].passengers.push(person);
+    Ok(())
+}
+
+// --- SWAPS A VEHICLE'S DRIVER WITH ONE OF ITS PASSENGERS ---
+// A minibus keeps its registration; a car group switches to the new driver's own car, so it must be big enough
+pub fn make_driver(groups: &mut [TransportGroup], g_idx: usize, p_idx: usize, on: NaiveDate) -> Result<(), String> {
+    let group = &groups[g_idx];
+    check_can_drive(&group.passengers[p_idx], group, group.passengers.len(), on)?;
+
+    let group          = &mut groups[g_idx];
+    let new_driver     = group.passengers.remove(p_idx);
+    let old_driver     = std::mem::replace(&mut group.driver, new_driver);
+    group.passengers.insert(0, old_driver);
+
+    if !group.is_minibus() {
+        let car = TransportGroup::car(group.driver.clone(), &group.destination, &group.departure_time);
+        *group = TransportGroup { passengers: std::mem::take(&mut group.passengers), equipment: std::mem::take(&mut group.equipment), ..car };
+    }
+    Ok(())
+}
+
+// --- PUTS LOCKED PEOPLE BACK IN THEIR CHOSEN VEHICLES AFTER AN ALLOCATION, RETURNING NOTES ON ANY THAT COULDN'T BE ---
+// Moves are swaps wherever a vehicle is full, so nobody loses their seat; the routes need planning again afterwards
+pub fn apply_locks(groups: &mut Vec<TransportGroup>, locks: &[Lock], on: NaiveDate) -> Vec<String> {
+    let locked: HashSet<&str> = locks.iter().map(|l| l.student_id.as_str()).collect();
+    let mut notes = Vec::new();
+
+    for lock in locks {
+        let Some((from, role)) = find_person(groups, &lock.student_id) else { continue };    // Not travelling this time (or left over)
+        let name = if let Some(p_idx) = role { groups[from].passengers[p_idx].name.clone() } else { groups[from].driver.name.clone() };
+
+        // A locked car that the allocator didn't use this time goes back on the road with its owner driving
+        let target = groups.iter().position(|g| g.leg == Leg::Outbound && g.registration == lock.registration && same_departure(g, &groups[from]));
+        let to = match (target, role) {
+            (Some(to), _) => to,
+            (None, Some(p_idx)) if lock.driving && groups[from].passengers[p_idx].car.as_ref().is_some_and(|c| c.registration == lock.registration) => {
+                let owner = groups[from].passengers.remove(p_idx);
+                let car   = TransportGroup::car(owner, &groups[from].destination, &groups[from].departure_time);
+                groups.insert(from + 1, car);
+                continue;
+            }
+            (None, _) => {
+                notes.push(format!("{} is locked to {}, which isn't on their departure this time.", name, lock.registration));
+                continue;
+            }
+        };
+
+        let result = match (lock.driving, role) {
+            (false, Some(_)) | (true, None) if from == to => Ok(()),
+
+            // Passenger elsewhere -> passenger here (swapping with someone unlocked if it's full)
+            (false, Some(p_idx)) => {
+                if groups[to].free_seats() > 0 {
+                    let person = groups[from].passengers.remove(p_idx);
+                    groups[to].passengers.push(person);
+                    Ok(())
+                } else if let Some(q_idx) = groups[to].passengers.iter().position(|q| !locked.contains(q.student_id.as_str())) {
+                    let person    = groups[from].passengers.remove(p_idx);
+                    let displaced = std::mem::replace(&mut groups[to].passengers[q_idx], person);
+                    groups[from].passengers.insert(p_idx, displaced);
+                    Ok(())
+                } else {
+                    Err(format!("{} is full of locked people", groups[to].vehicle_label))
+                }
+            }
+
+            // Passenger here -> driver here
+            (true, Some(p_idx)) if from == to => make_driver(groups, to, p_idx, on),
+
+            // Passenger elsewhere -> driver here, with the current driver taking their seat
+            (true, Some(p_idx)) => {
+                let passengers = groups[to].passengers.len();
+                check_can_drive(&groups[from].passengers[p_idx], &groups[to], passengers, on).and_then(|_| {
+                    if !groups[to].is_minibus() {
+                        return Err(format!("{} belongs to {}", groups[to].vehicle_label, groups[to].driver.name));
+                    }
+                    let person     = groups[from].passengers.remove(p_idx);
+                    let old_driver = std::mem::replace(&mut groups[to].driver, person);
+                    groups[from].passengers.insert(p_idx, old_driver);
+                    Ok(())
+                })
+            }
+
+            // Driving one minibus -> driving another, swapping with its driver
+            (true, None) => {
+                let (a, b) = (&groups[from], &groups[to]);
+                if !a.is_minibus() || !b.is_minibus() {
+                    Err("cars can only be driven by their owners".to_string())
+                } else if !b.driver.can_drive_minibus(on) {
+                    Err(format!("{} isn't licensed to drive a minibus", b.driver.name))
+                } else {
+                    let driver = groups[from].driver.clone();
+                    groups[from].driver = std::mem::replace(&mut groups[to].driver, driver);
+                    Ok(())
+                }
+            }
+
+            // Driving -> passenger, if someone else on board can take over
+            (false, None) => {
+                let stand_in = groups[from].passengers.iter().position(|q| !locked.contains(q.student_id.as_str()) && check_can_drive(q, &groups[from], groups[from].passengers.len(), on).is_ok());
+                match stand_in {
+                    Some(q_idx) if groups[to].free_seats() > 0 => {
+                        make_driver(groups, from, q_idx, on).map(|_| {
+                            let person = groups[from].passengers.remove(0);
+                            groups[to].passengers.push(person);
+                        })
+                    }
+                    Some(_) => Err(format!("{} is full", groups[to].vehicle_label)),
+                    None => Err("nobody else in their vehicle can drive it".to_string()),
+                }
+            }
+        };
+
+        if let Err(reason) = result {
+            notes.push(format!("Couldn't keep {}'s lock on {}: {}.", name, lock.registration, reason));
+        }
+    }
+    notes
+}
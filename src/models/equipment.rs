@@ -0,0 +1,381 @@
+use std::collections::HashSet;
+use std::cmp::Reverse;
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize};
+use super::{Person, Minibus, Session, Destination, TravelMatrix, TransportGroup, Leg, FleetSchedule};
+use super::constraints::Constraint;
+use super::edit::{check_can_drive, make_driver};
+use super::venue::venue_label;
+use super::credentials::Licensed;
+
+// --- WHAT A VEHICLE CAN CARRY BESIDES ITS PASSENGERS (SHARED BY MINIBUSES AND CARS) ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Haulage {
+    #[serde(default)]
+    pub tow_capable: bool,                  // Rated to tow a boat trailer
+    #[serde(default)]
+    pub tow_bar_fitted: bool,               // Tow bar actually on the vehicle at the moment
+    #[serde(default)]
+    pub cargo_slots: u8,                    // Boot space, in cargo slots (anything over this takes passenger seats)
+}
+
+impl Haulage {
+    pub fn can_tow(&self) -> bool {
+        self.tow_capable && self.tow_bar_fitted
+    }
+}
+
+// --- THINGS A SESSION MAY NEED TAKING TO THE WATER ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EquipmentKind {
+    #[default]
+    Trailer,                                // Towed, so it needs a tow bar and a driver licensed to tow
+    Blades,                                 // One set per slot
+    Ergs,
+    LaunchFuel,                             // One can per slot
+}
+
+impl EquipmentKind {
+    pub const ALL: [EquipmentKind; 4] = [EquipmentKind::Trailer, EquipmentKind::Blades, EquipmentKind::Ergs, EquipmentKind::LaunchFuel];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EquipmentKind::Trailer => "Trailer",
+            EquipmentKind::Blades => "Blades",
+            EquipmentKind::Ergs => "Ergs",
+            EquipmentKind::LaunchFuel => "Launch fuel",
+        }
+    }
+
+    // Cargo slots taken by one of these (a passenger seat holds one slot)
+    pub fn slots(&self) -> usize {
+        match self {
+            EquipmentKind::Trailer => 0,
+            EquipmentKind::Blades => 1,
+            EquipmentKind::Ergs => 2,
+            EquipmentKind::LaunchFuel => 1,
+        }
+    }
+
+    // --- e.g. "trailer", "8 sets of blades", "2 ergs" ---
+    pub fn describe(&self, quantity: usize) -> String {
+        let (one, many) = match self {
+            EquipmentKind::Trailer => ("trailer", "trailers"),
+            EquipmentKind::Blades => ("set of blades", "sets of blades"),
+            EquipmentKind::Ergs => ("erg", "ergs"),
+            EquipmentKind::LaunchFuel => ("can of launch fuel", "cans of launch fuel"),
+        };
+        match quantity {
+            1 if *self == EquipmentKind::Trailer => one.to_string(),
+            1 => format!("1 {}", one),
+            n => format!("{} {}", n, many),
+        }
+    }
+}
+
+// --- EQUIPMENT A SESSION NEEDS TAKING ON ONE DEPARTURE (e.g. THE TRAILER TO AUCHENSTARRY AT 07:00) ---
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EquipmentNeed {
+    pub kind: EquipmentKind,
+    pub quantity: u8,
+    pub destination: Destination,
+    pub departure_time: String,
+}
+
+impl EquipmentNeed {
+    // Times are compared trimmed, the same way the validation reads them
+    pub fn is_for(&self, dest: &Destination, time: &str) -> bool {
+        self.destination == *dest && self.departure_time.trim() == time.trim()
+    }
+}
+
+// --- WHAT ONE DEPARTURE STILL HAS TO CARRY, HANDED TO ITS VEHICLES AS THE STRATEGIES CHOOSE THEM ---
+// The kit is loaded before any passengers are seated, so it takes up capacity rather than being squeezed in afterwards
+#[derive(Debug, Clone, Default)]
+pub struct DepartureKit {
+    pub trailers: usize,                    // Each needs a vehicle that can tow and a driver licensed to tow it
+    pub cargo: Vec<EquipmentKind>,          // One entry per item, bulkiest first
+}
+
+impl DepartureKit {
+    pub fn for_departure(needs: &[EquipmentNeed], dest: &Destination, time: &str) -> Self {
+        let mut kit = DepartureKit::default();
+        for need in needs.iter().filter(|n| n.is_for(dest, time)) {
+            match need.kind {
+                EquipmentKind::Trailer => kit.trailers += need.quantity as usize,
+                kind => kit.cargo.extend(std::iter::repeat_n(kind, need.quantity as usize)),
+            }
+        }
+        kit.cargo.sort_by_key(|k| Reverse(k.slots()));
+        kit
+    }
+
+    pub fn cargo_slots(&self) -> usize {
+        self.cargo.iter().map(|k| k.slots()).sum()
+    }
+
+    // --- A FREE MINIBUS THAT CAN TOW AND SOMEONE IN THE POOL LICENSED TO DRIVE AND TOW WITH IT, WHILE A TRAILER IS WAITING ---
+    pub fn towing_minibus(
+        &self,
+        pool: &[Person],
+        schedule: &FleetSchedule,
+        (dest, time): (&Destination, &str),
+        matrix: &TravelMatrix,
+        licensed: &Licensed,
+    ) -> Option<(Minibus, usize)> {
+        if self.trailers == 0 { return None; }
+        let d_idx = pool.iter().position(|p| licensed.minibus(p) && p.can_tow(true))?;
+        let mb    = schedule.minibuses.iter().find(|m| m.kit.can_tow() && schedule.is_free(m, dest, time, matrix))?;
+        Some((mb.clone(), d_idx))
+    }
+
+    // --- SOMEONE IN THE POOL WHOSE OWN CAR CAN TOW AND WHO IS LICENSED TO TOW WITH IT, WHILE A TRAILER IS WAITING ---
+    pub fn towing_car(&self, pool: &[Person]) -> Option<usize> {
+        if self.trailers == 0 { return None; }
+        pool.iter().position(|p| p.can_tow(false) && p.car.as_ref().is_some_and(|c| c.kit.can_tow()))
+    }
+
+    // --- LOADS WHAT'S STILL WAITING ONTO THE VEHICLES CHOSEN SO FAR: TRAILERS WHERE VEHICLE AND DRIVER CAN TOW, ---
+    // --- THEN CARGO WHERE IT TAKES THE FEWEST SEATS (BOOTS BEFORE SEATS, SINCE THE PASSENGERS CAN TAKE ANOTHER VEHICLE) ---
+    pub fn reserve(&mut self, groups: &mut [TransportGroup]) {
+        while self.trailers > 0 {
+            let Some(g) = groups.iter_mut().find(|g| !g.is_towing() && g.kit.can_tow() && g.driver.can_tow(g.is_minibus())) else { break };
+            g.add_load(EquipmentKind::Trailer);
+            self.trailers -= 1;
+        }
+
+        let mut left = Vec::new();
+        for kind in std::mem::take(&mut self.cargo) {
+            let target = groups.iter_mut()
+                .filter(|g| g.extra_seats_for(kind.slots()) <= g.free_seats())
+                .min_by_key(|g| (g.extra_seats_for(kind.slots()), Reverse(g.kit.cargo_slots)));
+            match target {
+                Some(g) => g.add_load(kind),
+                None => left.push(kind),
+            }
+        }
+        self.cargo = left;
+    }
+}
+
+// --- EQUIPMENT LOADED ON A VEHICLE ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Load {
+    pub kind: EquipmentKind,
+    pub quantity: usize,
+}
+
+// --- PUTS ANY OF THE SESSION'S EQUIPMENT THE STRATEGY COULDN'T FIT ON A VEHICLE THAT CAN TAKE IT, AFTER THE PEOPLE ---
+// Trailers go first: on a vehicle with a tow bar and a driver licensed to tow, handing the wheel to a licensed
+// passenger, swapping a minibus for a free one with a tow bar, or as a last resort putting a licensed passenger's own
+// car back on the road to tow it. Cargo then fills boots before spare seats.
+// Nobody locked or told to drive is moved off the wheel. The routes need planning again afterwards.
+pub fn load_equipment(groups: &mut Vec<TransportGroup>, session: &Session, minibuses: &[Minibus], matrix: &TravelMatrix) {
+    let mut pinned: HashSet<&str> = session.locks.iter().map(|l| l.student_id.as_str()).collect();
+    pinned.extend(session.constraints.iter().filter_map(|c| if let Constraint::MustDrive(id) = c { Some(id.as_str()) } else { None }));
+    let locked_vehicles: HashSet<&str> = session.locks.iter().map(|l| l.registration.as_str()).collect();
+    let on = session.outing_date();
+
+    // Only what's still missing, since the strategies load most of it while choosing the vehicles
+    let mut missing = tally(groups, &session.equipment);
+    missing.retain(|(_, wanted, loaded)| loaded < wanted);
+
+    for (need, wanted, loaded) in missing.iter().filter(|(n, _, _)| n.kind == EquipmentKind::Trailer) {
+        for _ in *loaded..*wanted {
+            if !hitch_trailer(groups, need, &pinned, &locked_vehicles, minibuses, matrix, on) { break; }
+        }
+    }
+
+    // Bulkiest cargo first, so the small things fill the gaps
+    missing.retain(|(n, _, _)| n.kind != EquipmentKind::Trailer);
+    missing.sort_by_key(|(n, _, _)| Reverse(n.kind.slots()));
+    for (need, wanted, loaded) in missing {
+        for _ in loaded..wanted {
+            if !stow(groups, need) { break; }
+        }
+    }
+}
+
+// --- EACH KIND OF EQUIPMENT ON EACH DEPARTURE, AS (FIRST NEED FOR IT, HOW MANY ARE WANTED, HOW MANY ARE LOADED) ---
+// Needs for the same thing on the same departure are added up before counting what was loaded
+fn tally<'a>(groups: &[TransportGroup], needs: &'a [EquipmentNeed]) -> Vec<(&'a EquipmentNeed, usize, usize)> {
+    let mut counts: Vec<(&EquipmentNeed, usize, usize)> = Vec::new();
+    for need in needs {
+        if counts.iter().any(|(n, _, _)| n.kind == need.kind && n.is_for(&need.destination, &need.departure_time)) { continue; }
+
+        let wanted: usize = needs.iter()
+            .filter(|n| n.kind == need.kind && n.is_for(&need.destination, &need.departure_time))
+            .map(|n| n.quantity as usize)
+            .sum();
+        let loaded: usize = groups.iter()
+            .filter(|g| g.leg == Leg::Outbound && need.is_for(&g.destination, &g.departure_time))
+            .flat_map(|g| &g.equipment)
+            .filter(|l| l.kind == need.kind)
+            .map(|l| l.quantity)
+            .sum();
+        counts.push((need, wanted, loaded));
+    }
+    counts
+}
+
+// --- FINDS OR MAKES A VEHICLE ON THE DEPARTURE THAT CAN TOW ONE TRAILER, RETURNING FALSE IF NONE CAN ---
+fn hitch_trailer(
+    groups: &mut Vec<TransportGroup>,
+    need: &EquipmentNeed,
+    pinned: &HashSet<&str>,
+    locked_vehicles: &HashSet<&str>,
+    minibuses: &[Minibus],
+    matrix: &TravelMatrix,
+    on: NaiveDate,
+) -> bool {
+    let on_departure = |g: &TransportGroup| g.leg == Leg::Outbound && need.is_for(&g.destination, &g.departure_time) && !g.is_towing();
+
+    // A vehicle that can tow, already with a licensed driver
+    if let Some(g_idx) = groups.iter().position(|g| on_departure(g) && g.kit.can_tow() && g.driver.can_tow(g.is_minibus())) {
+        groups[g_idx].add_load(EquipmentKind::Trailer);
+        return true;
+    }
+
+    // A licensed passenger taking the wheel of a vehicle that would then tow (a car group switches to their own car)
+    let swap = groups.iter().enumerate()
+        .filter(|(_, g)| on_departure(g) && !pinned.contains(g.driver.student_id.as_str()) && !locked_vehicles.contains(g.registration.as_str()))
+        .find_map(|(g_idx, g)| {
+            let p_idx = g.passengers.iter().position(|p| {
+                let hitch = if g.is_minibus() { g.kit.can_tow() } else { p.car.as_ref().is_some_and(|c| c.kit.can_tow()) };
+                hitch && p.can_tow(g.is_minibus()) && !pinned.contains(p.student_id.as_str()) && check_can_drive(p, g, g.passengers.len(), on).is_ok()
+            })?;
+            Some((g_idx, p_idx))
+        });
+    if let Some((g_idx, p_idx)) = swap
+        && make_driver(groups, g_idx, p_idx, on).is_ok()
+    {
+        groups[g_idx].add_load(EquipmentKind::Trailer);
+        return true;
+    }
+
+    // A minibus swapped for a free one with a tow bar and enough seats, with a licensed passenger taking the wheel if need be
+    let mut schedule = FleetSchedule::new(minibuses);
+    for g in groups.iter().filter(|g| g.leg == Leg::Outbound && g.is_minibus()) {
+        if let Some(mb) = minibuses.iter().find(|m| m.registration == g.registration) {
+            schedule.book(mb, &g.driver.name, &g.destination, &g.departure_time, matrix);
+        }
+    }
+    for group in groups.iter_mut() {
+        if !on_departure(group) || !group.is_minibus() || locked_vehicles.contains(group.registration.as_str()) { continue; }
+
+        let new_driver = if group.driver.can_tow(true) {
+            None
+        } else if pinned.contains(group.driver.student_id.as_str()) {
+            continue;
+        } else {
+            let licensed = group.passengers.iter()
+                .position(|p| p.can_drive_minibus(on) && p.can_tow(true) && !pinned.contains(p.student_id.as_str()));
+            match licensed {
+                Some(p_idx) => Some(p_idx),
+                None => continue,
+            }
+        };
+
+        let needed = 1 + group.passengers.len() + group.cargo_seats();
+        let Some(mb) = schedule.minibuses.iter()
+            .find(|m| m.kit.can_tow() && m.seats as usize >= needed && schedule.is_free(m, &group.destination, &group.departure_time, matrix))
+            .cloned()
+        else { continue };
+
+        if let Some(p_idx) = new_driver {
+            let driver     = group.passengers.remove(p_idx);
+            let old_driver = std::mem::replace(&mut group.driver, driver);
+            group.passengers.insert(0, old_driver);
+        }
+        let swapped = TransportGroup::minibus(group.driver.clone(), &mb, &group.destination, &group.departure_time);
+        *group = TransportGroup {
+            passengers: std::mem::take(&mut group.passengers),
+            equipment: std::mem::take(&mut group.equipment),
+            ..swapped
+        };
+        group.add_load(EquipmentKind::Trailer);
+        return true;
+    }
+
+    // A licensed passenger whose own car has a tow bar drives it, even though it's one more vehicle
+    let owner = groups.iter().enumerate()
+        .filter(|(_, g)| on_departure(g))
+        .find_map(|(g_idx, g)| {
+            let p_idx = g.passengers.iter().position(|p| {
+                p.can_tow(false) && !pinned.contains(p.student_id.as_str()) && p.car.as_ref().is_some_and(|c| c.kit.can_tow())
+            })?;
+            Some((g_idx, p_idx))
+        });
+    if let Some((g_idx, p_idx)) = owner {
+        let owner   = groups[g_idx].passengers.remove(p_idx);
+        let mut car = TransportGroup::car(owner, &need.destination, &groups[g_idx].departure_time);
+        car.add_load(EquipmentKind::Trailer);
+        groups.insert(g_idx + 1, car);
+        return true;
+    }
+    false
+}
+
+// --- PUTS ONE ITEM OF CARGO WHERE IT COSTS THE FEWEST SEATS (AS `DepartureKit::reserve` DOES), RETURNING FALSE IF THE DEPARTURE IS FULL ---
+fn stow(groups: &mut [TransportGroup], need: &EquipmentNeed) -> bool {
+    let target = groups.iter().enumerate()
+        .filter(|(_, g)| g.leg == Leg::Outbound && need.is_for(&g.destination, &g.departure_time))
+        .filter_map(|(g_idx, g)| {
+            let seats = g.extra_seats_for(need.kind.slots());
+            (seats <= g.free_seats()).then_some((g_idx, seats, g.kit.cargo_slots as usize))
+        })
+        .min_by_key(|&(_, seats, boot)| (seats, Reverse(boot)))
+        .map(|(g_idx, _, _)| g_idx);
+
+    match target {
+        Some(g_idx) => { groups[g_idx].add_load(need.kind); true }
+        None => false,
+    }
+}
+
+// --- ANY EQUIPMENT STILL WITHOUT A VEHICLE, OR TOWED BY SOMEONE WHO ISN'T LICENSED ---
+pub fn equipment_problems(groups: &[TransportGroup], needs: &[EquipmentNeed], matrix: &TravelMatrix) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for (need, wanted, loaded) in tally(groups, needs) {
+        if loaded >= wanted { continue; }
+
+        let why = if need.kind == EquipmentKind::Trailer {
+            "it needs a vehicle with a tow bar and a driver licensed to tow"
+        } else {
+            "there isn't enough boot space or spare seats"
+        };
+        let missing = match loaded {
+            0 => need.kind.describe(wanted),
+            _ => format!("{} of {}", wanted - loaded, need.kind.describe(wanted)),
+        };
+        problems.push(format!(
+            "Equipment not loaded: {} for {} {} - {}.",
+            missing, need.departure_time.trim(), venue_label(matrix.venues, &need.destination), why,
+        ));
+    }
+
+    for g in groups.iter().filter(|g| g.leg == Leg::Outbound && g.is_towing() && !g.driver.can_tow(g.is_minibus())) {
+        problems.push(format!("Equipment problem: {} is towing the trailer with {} but isn't licensed to tow.", g.driver.name, g.vehicle_label));
+    }
+    problems
+}
+
+// --- WHY A PERSON COULDN'T TAKE OVER A VEHICLE CARRYING THIS EQUIPMENT, IF THEY COULDN'T ---
+pub fn check_can_carry(person: &Person, group: &TransportGroup) -> Result<(), String> {
+    if group.is_towing() && !person.can_tow(group.is_minibus()) {
+        return Err(format!("{} isn't licensed to tow the trailer.", person.name));
+    }
+    if group.is_minibus() { return Ok(()); }
+
+    // A car group changes to the new driver's car, so the load has to suit it
+    let Some(car) = &person.car else { return Ok(()) };
+    if group.is_towing() && !car.kit.can_tow() {
+        return Err(format!("{}'s {} can't tow the trailer.", person.name, car.vehicle_type));
+    }
+    let seats = group.cargo_slots().saturating_sub(car.kit.cargo_slots as usize);
+    if (car.seats as usize) < group.passengers.len() + 1 + seats {
+        return Err(format!("{}'s {} hasn't room for everyone and the {}.", person.name, car.vehicle_type, group.load_summary()));
+    }
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use chrono::NaiveDate;
+use super::{Leg, TravelMatrix};
+use super::history::{DriverHistory, DriveRecord};
+use super::venue::venue_label;
+
+// --- WHAT THE CLUB PAYS BACK FOR DRIVING A PRIVATE CAR ---
+// Money is kept in whole pence throughout, and only rounded once per journey
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpenseRates {
+    pub per_mile: u64,                      // Pence per mile driven
+    pub per_passenger_mile: u64,            // Pence per mile for each passenger carried
+}
+
+impl ExpenseRates {
+    // --- READS THE RATES AS TYPED IN POUNDS (E.G. "0.45") ---
+    pub fn parse(per_mile: &str, per_passenger_mile: &str) -> Result<Self, String> {
+        let rate = |label: &str, text: &str| parse_pounds(text)
+            .ok_or_else(|| format!("{} '{}' must be an amount in pounds (e.g. 0.45).", label, text));
+        Ok(ExpenseRates {
+            per_mile:           rate("Rate per mile", per_mile)?,
+            per_passenger_mile: rate("Passenger rate per mile", per_passenger_mile)?,
+        })
+    }
+}
+
+impl Default for ExpenseRates {
+    // The HMRC approved rates for cars (45p a mile, plus 5p a mile per passenger)
+    fn default() -> Self {
+        ExpenseRates { per_mile: 45, per_passenger_mile: 5 }
+    }
+}
+
+// --- ONE PUBLISHED CAR JOURNEY BEING CLAIMED FOR ---
+#[derive(Debug, Clone)]
+pub struct ExpenseTrip {
+    pub date: String,
+    pub session: String,
+    pub leg: Leg,
+    pub vehicle_label: String,
+    pub route: String,                      // Every stop in the order driven, venue included (e.g. "Library > Gym > Strathclyde Park")
+    pub miles: f64,
+    pub passengers: usize,
+    pub passenger_miles: f64,               // Each passenger's own miles (their stop to the venue, or the venue to their drop-off) added up
+}
+
+impl ExpenseTrip {
+    pub fn mileage(&self, rates: &ExpenseRates) -> u64 {
+        (self.miles * rates.per_mile as f64).round() as u64
+    }
+
+    pub fn supplement(&self, rates: &ExpenseRates) -> u64 {
+        (self.passenger_miles * rates.per_passenger_mile as f64).round() as u64
+    }
+}
+
+// --- EVERYTHING ONE DRIVER CAN CLAIM FOR IN THE DATE RANGE ---
+#[derive(Debug, Clone)]
+pub struct DriverClaim {
+    pub name: String,
+    pub student_id: String,
+    pub trips: Vec<ExpenseTrip>,
+}
+
+impl DriverClaim {
+    pub fn miles(&self) -> f64 {
+        self.trips.iter().map(|t| t.miles).sum()
+    }
+
+    pub fn mileage(&self, rates: &ExpenseRates) -> u64 {
+        self.trips.iter().map(|t| t.mileage(rates)).sum()
+    }
+
+    pub fn supplement(&self, rates: &ExpenseRates) -> u64 {
+        self.trips.iter().map(|t| t.supplement(rates)).sum()
+    }
+
+    pub fn total(&self, rates: &ExpenseRates) -> u64 {
+        self.mileage(rates) + self.supplement(rates)
+    }
+}
+
+// --- MILEAGE CLAIMS FOR EVERY CAR DRIVER ON THE SHEETS PUBLISHED BETWEEN TWO DATES (INCLUSIVE) ---
+// Minibuses are the club's own, so only journeys in people's own cars are claimed for
+#[derive(Debug, Clone)]
+pub struct ExpenseReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub rates: ExpenseRates,
+    pub claims: Vec<DriverClaim>,           // Alphabetical by driver, trips in date order
+}
+
+impl ExpenseReport {
+    pub fn build(history: &DriverHistory, from: NaiveDate, to: NaiveDate, rates: ExpenseRates, matrix: &TravelMatrix) -> Self {
+        let mut claims: Vec<DriverClaim> = Vec::new();
+
+        let mut drives: Vec<&DriveRecord> = history.drives.iter()
+            .filter(|d| !d.minibus)
+            .filter(|d| NaiveDate::parse_from_str(&d.date, "%Y-%m-%d").is_ok_and(|day| day >= from && day <= to))
+            .collect();
+        drives.sort_by(|a, b| (&a.date, &a.departure_time).cmp(&(&b.date, &b.departure_time)));
+
+        for drive in drives {
+            let trip = trip_for(drive, matrix);
+            match claims.iter_mut().find(|c| c.student_id == drive.student_id) {
+                Some(claim) => claim.trips.push(trip),
+                None => claims.push(DriverClaim { name: drive.name.clone(), student_id: drive.student_id.clone(), trips: vec![trip] }),
+            }
+        }
+        claims.sort_by(|a, b| a.name.cmp(&b.name));
+
+        ExpenseReport { from, to, rates, claims }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.claims.iter().map(|c| c.total(&self.rates)).sum()
+    }
+
+    // --- ONE ROW PER TRIP, THEN A TOTAL ROW FOR EACH DRIVER ---
+    pub fn to_csv(&self) -> String {
+        let mut lines = vec![
+            "Driver,Student ID,Date,Session,Leg,Vehicle,Route,Miles,Passengers,Passenger miles,Mileage (GBP),Passenger supplement (GBP),Total (GBP)".to_string(),
+        ];
+        let rates = &self.rates;
+
+        for claim in &self.claims {
+            for trip in &claim.trips {
+                let leg = if trip.leg == Leg::Return { "Return" } else { "Outbound" };
+                lines.push([
+                    csv_field(&claim.name), csv_field(&claim.student_id), csv_field(&trip.date), csv_field(&trip.session),
+                    leg.to_string(), csv_field(&trip.vehicle_label), csv_field(&trip.route),
+                    format!("{:.1}", trip.miles), trip.passengers.to_string(), format!("{:.1}", trip.passenger_miles),
+                    pounds(trip.mileage(rates)), pounds(trip.supplement(rates)),
+                    pounds(trip.mileage(rates) + trip.supplement(rates)),
+                ].join(","));
+            }
+            lines.push([
+                csv_field(&claim.name), csv_field(&claim.student_id), "Total".to_string(), String::new(), String::new(), String::new(), String::new(),
+                format!("{:.1}", claim.miles()), String::new(), String::new(),
+                pounds(claim.mileage(rates)), pounds(claim.supplement(rates)), pounds(claim.total(rates)),
+            ].join(","));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+// --- READS A FROM / TO DATE RANGE (YYYY-MM-DD, INCLUSIVE) ---
+pub fn parse_range(from: &str, to: &str) -> Result<(NaiveDate, NaiveDate), String> {
+    let date = |label: &str, text: &str| {
+        NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").map_err(|_| format!("{} date '{}' must be written as YYYY-MM-DD.", label, text))
+    };
+    let (from, to) = (date("From", from)?, date("To", to)?);
+    if from > to {
+        return Err(format!("The range starts ({}) after it ends ({}).", from, to));
+    }
+    Ok((from, to))
+}
+
+// --- THE MILES DRIVEN ON ONE JOURNEY: STOP TO STOP, THEN ON TO THE VENUE (OR FROM IT, ON THE WAY BACK) ---
+// Each passenger counts from the stop they got in at to the venue, or from the venue to the stop they got out at
+fn trip_for(drive: &DriveRecord, matrix: &TravelMatrix) -> ExpenseTrip {
+    let hops: Vec<f64> = drive.stops.windows(2).map(|pair| matrix.miles_between(&pair[0], &pair[1])).collect();
+    let venue_leg = match drive.leg {
+        Leg::Outbound => drive.stops.last(),
+        Leg::Return   => drive.stops.first(),
+    };
+    let to_venue = venue_leg.map_or(0.0, |stop| matrix.miles_to_venue(stop, &drive.destination));
+    let miles    = hops.iter().sum::<f64>() + to_venue;
+
+    // Miles between the venue and each stop, along the route as it was driven
+    let from_venue = |s_idx: usize| to_venue + match drive.leg {
+        Leg::Outbound => hops[s_idx..].iter().sum::<f64>(),
+        Leg::Return   => hops[..s_idx].iter().sum::<f64>(),
+    };
+    let passenger_miles = if drive.riders.len() == drive.stops.len() {
+        drive.riders.iter().enumerate().map(|(s_idx, &riders)| riders as f64 * from_venue(s_idx)).sum()
+    } else {
+        miles * drive.passengers as f64                                     // Older records don't say who got in where
+    };
+
+    let venue     = venue_label(matrix.venues, &drive.destination).to_string();
+    let mut route = drive.stops.clone();
+    match drive.leg {
+        Leg::Outbound => route.push(venue),
+        Leg::Return   => route.insert(0, venue),
+    }
+
+    ExpenseTrip {
+        date:            drive.date.clone(),
+        session:         drive.session.clone(),
+        leg:             drive.leg,
+        vehicle_label:   drive.vehicle_label.clone(),
+        route:           route.join(" > "),
+        miles,
+        passengers:      drive.passengers,
+        passenger_miles,
+    }
+}
+
+// --- AN AMOUNT TYPED IN POUNDS (E.G. "0.45" OR "1") AS PENCE - NONE IF IT ISN'T ONE, OR GOES PAST THE PENNY ---
+fn parse_pounds(text: &str) -> Option<u64> {
+    let (whole, fraction) = text.trim().split_once('.').unwrap_or((text.trim(), ""));
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || fraction.len() > 2 || !digits(whole) || !digits(fraction) {
+        return None;
+    }
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse().ok()? };
+    let pence: u64 = format!("{:0<2}", fraction).parse().ok()?;
+    whole.checked_mul(100)?.checked_add(pence)
+}
+
+// --- PENCE WRITTEN AS POUNDS, E.G. 1234 -> "12.34" ---
+pub fn pounds(pence: u64) -> String {
+    format!("{}.{:02}", pence / 100, pence % 100)
+}
+
+// Quoted when it holds a comma, quote or line break
+pub fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
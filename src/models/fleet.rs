@@ -0,0 +1,135 @@
+use chrono::{NaiveTime, Duration};
+use std::cmp::Reverse;
+use super::{Minibus, Destination, TravelMatrix, TransportGroup, Venue, Leg};
+use super::venue::{find_venue, venue_label};
+
+// --- ONE STRETCH OF TIME A VEHICLE (AND ITS DRIVER) IS AWAY FROM WHERE IT STARTED ---
+// A vehicle waits at the venue for its crew, so it is out from the start of its pickup round until it is driven back
+#[derive(Debug, Clone)]
+pub struct Booking {
+    pub registration: String,
+    pub driver: String,                     // Name of whoever is driving it
+    pub label: String,                      // e.g. "07:00 Strathclyde Park", used when reporting clashes
+    pub out: NaiveTime,                     // Leaves its depot (or its driver's pickup point)
+    pub back: NaiveTime,                    // Back there after the session and the drive home
+}
+
+impl Booking {
+    pub fn overlaps(&self, out: NaiveTime, back: NaiveTime) -> bool {
+        self.out < back && out < self.back
+    }
+}
+
+// --- THE MINIBUS FLEET AND EVERY BOOKING FOR THE DAY, SO A MINIBUS BACK FROM AN EARLY WAVE CAN TAKE A LATER ONE ---
+#[derive(Debug, Default, Clone)]
+pub struct FleetSchedule {
+    pub minibuses: Vec<Minibus>,            // Largest first, so the biggest free minibus is always offered first
+    pub bookings: Vec<Booking>,
+}
+
+impl FleetSchedule {
+    const PICKUP_ALLOWANCE_MINUTES: i64 = 30;   // Time allowed for the pickup round before a departure (until the route is planned)
+
+    pub fn new(minibuses: &[Minibus]) -> Self {
+        let mut minibuses = minibuses.to_vec();
+        minibuses.sort_by_key(|m| Reverse(m.seats));
+        FleetSchedule { minibuses, bookings: Vec::new() }
+    }
+
+    // --- ESTIMATED WINDOW FOR A MINIBUS TAKING A DEPARTURE, BEFORE ITS PICKUPS ARE KNOWN ---
+    // Unparseable times book the whole day, which is how the fleet used to be treated
+    pub fn estimate(mb: &Minibus, dest: &Destination, time: &str, matrix: &TravelMatrix) -> (NaiveTime, NaiveTime) {
+        let Ok(departure) = NaiveTime::parse_from_str(time.trim(), "%H:%M") else { return (NaiveTime::MIN, Self::end_of_day()) };
+        let out     = Self::minus(departure, Self::PICKUP_ALLOWANCE_MINUTES);
+        let arrival = Self::plus(departure, matrix.to_venue(&mb.depot, dest) as i64);
+        (out, Self::back_from(arrival, &mb.depot, dest, matrix))
+    }
+
+    // --- WHEN A VEHICLE ARRIVING AT A VENUE IS BACK WHERE IT STARTED (TIME AT THE VENUE PLUS THE DRIVE HOME) ---
+    fn back_from(arrival: NaiveTime, start: &str, dest: &Destination, matrix: &TravelMatrix) -> NaiveTime {
+        let stay = find_venue(matrix.venues, dest).map(|v| v.session_minutes).unwrap_or(Venue::default_session_minutes());
+        Self::plus(arrival, (stay + matrix.to_venue(start, dest)) as i64)
+    }
+
+    // --- LARGEST MINIBUS THAT IS FREE FOR THIS DEPARTURE ---
+    pub fn first_free(&self, dest: &Destination, time: &str, matrix: &TravelMatrix) -> Option<Minibus> {
+        self.minibuses.iter().find(|mb| self.is_free(mb, dest, time, matrix)).cloned()
+    }
+
+    pub fn is_free(&self, mb: &Minibus, dest: &Destination, time: &str, matrix: &TravelMatrix) -> bool {
+        let (out, back) = Self::estimate(mb, dest, time, matrix);
+        !self.bookings.iter().any(|b| b.registration == mb.registration && b.overlaps(out, back))
+    }
+
+    pub fn book(&mut self, mb: &Minibus, driver: &str, dest: &Destination, time: &str, matrix: &TravelMatrix) {
+        let (out, back) = Self::estimate(mb, dest, time, matrix);
+        self.bookings.push(Booking {
+            registration: mb.registration.clone(),
+            driver: driver.to_string(),
+            label: format!("{} {}", time, venue_label(matrix.venues, dest)),
+            out,
+            back,
+        });
+    }
+
+    // --- THE REAL WINDOWS ONCE ROUTES HAVE BEEN PLANNED (CARS INCLUDED, SINCE THEIR DRIVERS CAN CLASH TOO) ---
+    // A vehicle is back when its return journey finishes, or after the venue's usual session if it has none
+    pub fn from_groups(groups: &[TransportGroup], matrix: &TravelMatrix) -> Self {
+        let parse = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").ok();
+
+        let bookings = groups.iter().filter(|g| g.leg == Leg::Outbound).map(|g| {
+            let out = g.stops.first().and_then(|s| parse(&s.time)).unwrap_or(NaiveTime::MIN);
+            let return_leg = groups.iter()
+                .find(|r| r.leg == Leg::Return && r.registration == g.registration && r.driver.student_id == g.driver.student_id);
+            let back = match (return_leg.and_then(|r| parse(&r.arrival_time)), parse(&g.arrival_time)) {
+                (Some(home), _) => home,
+                (None, Some(arrival)) => Self::back_from(arrival, &g.pickup_location, &g.destination, matrix),
+                (None, None) => Self::end_of_day(),
+            };
+            Booking {
+                registration: g.registration.clone(),
+                driver: g.driver.name.clone(),
+                label: format!("{} {}", g.departure_time, venue_label(matrix.venues, &g.destination)),
+                out,
+                back,
+            }
+        }).collect();
+
+        FleetSchedule { minibuses: Vec::new(), bookings }
+    }
+
+    // --- ANY VEHICLE OR DRIVER NEEDED IN TWO PLACES AT ONCE ---
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut clashes = Vec::new();
+        for (i, a) in self.bookings.iter().enumerate() {
+            for b in &self.bookings[i + 1..] {
+                if !a.overlaps(b.out, b.back) { continue; }
+
+                if a.registration == b.registration {
+                    clashes.push(format!(
+                        "{} is out for {} ({} - {}) but also needed for {} from {}.",
+                        a.registration, a.label, a.out.format("%H:%M"), a.back.format("%H:%M"), b.label, b.out.format("%H:%M"),
+                    ));
+                } else if a.driver == b.driver {
+                    clashes.push(format!("{} would be driving for {} and {} at the same time.", a.driver, a.label, b.label));
+                }
+            }
+        }
+        clashes
+    }
+
+    // --- TIME ARITHMETIC THAT STOPS AT MIDNIGHT RATHER THAN WRAPPING ROUND ---
+    fn plus(time: NaiveTime, minutes: i64) -> NaiveTime {
+        let (result, wrapped) = time.overflowing_add_signed(Duration::minutes(minutes));
+        if wrapped != 0 { Self::end_of_day() } else { result }
+    }
+
+    fn minus(time: NaiveTime, minutes: i64) -> NaiveTime {
+        let (result, wrapped) = time.overflowing_sub_signed(Duration::minutes(minutes));
+        if wrapped != 0 { NaiveTime::MIN } else { result }
+    }
+
+    fn end_of_day() -> NaiveTime {
+        NaiveTime::from_hms_opt(23, 59, 59).unwrap()
+    }
+}
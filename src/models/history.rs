@@ -0,0 +1,167 @@
+use std::collections::{HashMap, HashSet};
+use chrono::{Datelike, NaiveDate};
+use serde::{Serialize, Deserialize};
+use super::{Session, TransportGroup, Leg, Destination};
+use super::constraints::Constraint;
+use super::edit::{check_can_drive, make_driver};
+
+// --- ONE VEHICLE DRIVEN ON A PUBLISHED TRANSPORT SHEET ---
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriveRecord {
+    pub date: String,                       // Day of the outing the sheet was published for (YYYY-MM-DD)
+    pub session: String,                    // Name of the session it was published from
+    pub student_id: String,
+    pub name: String,
+    pub registration: String,
+    pub vehicle_label: String,
+    pub minibus: bool,
+    pub leg: Leg,
+    pub departure_time: String,
+    pub destination: Destination,
+    pub stops: Vec<String>,                 // Pickup (or drop-off) points in the order they were driven
+    pub passengers: usize,
+    #[serde(default)]
+    pub riders: Vec<usize>,                 // Passengers getting in (or, on the way back, out) at each stop - empty in older files
+}
+
+impl DriveRecord {
+    fn day(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(&self.date, "%Y-%m-%d").ok()
+    }
+}
+
+// --- EVERY DRIVE FROM EVERY PUBLISHED SHEET, SAVED TO driver_history.json ---
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DriverHistory {
+    pub drives: Vec<DriveRecord>,
+}
+
+// --- HOW MUCH ONE PERSON DROVE IN A TERM ---
+#[derive(Debug, Clone)]
+pub struct DriverStats {
+    pub name: String,
+    pub minibus: usize,                     // Outbound minibus drives (the journey home counts with the trip out)
+    pub car: usize,
+    pub last_drove: String,
+}
+
+impl DriverStats {
+    pub fn total(&self) -> usize {
+        self.minibus + self.car
+    }
+}
+
+impl DriverHistory {
+
+    // --- ADDS THE DRIVERS FROM A PUBLISHED PLAN ---
+    // Anything already recorded for the same outing (its date and session) is replaced, so publishing again doesn't count twice
+    pub fn record(&mut self, groups: &[TransportGroup], date: NaiveDate, session: &str) {
+        let date = date.format("%Y-%m-%d").to_string();
+        self.drives.retain(|d| !(d.date == date && d.session == session));
+
+        self.drives.extend(groups.iter().map(|g| DriveRecord {
+            date:           date.clone(),
+            session:        session.to_string(),
+            student_id:     g.driver.student_id.clone(),
+            name:           g.driver.name.clone(),
+            registration:   g.registration.clone(),
+            vehicle_label:  g.vehicle_label.clone(),
+            minibus:        g.is_minibus(),
+            leg:            g.leg,
+            departure_time: g.departure_time.clone(),
+            destination:    g.destination.clone(),
+            stops:          g.stops.iter().map(|s| s.location.clone()).collect(),
+            passengers:     g.passengers.len(),
+            riders:         g.stops.iter().map(|s| s.people.iter().filter(|name| **name != g.driver.name).count()).collect(),
+        }));
+    }
+
+    // --- THE LAST DAY SOMEONE DROVE (NONE IF THEY NEVER HAVE) ---
+    pub fn last_drove(&self, student_id: &str) -> Option<NaiveDate> {
+        self.drives.iter()
+            .filter(|d| d.student_id == student_id && d.leg == Leg::Outbound)
+            .filter_map(|d| d.day())
+            .max()
+    }
+
+    // --- DRIVES PER PERSON FOR EACH TERM, NEWEST TERM FIRST AND MOST DRIVES FIRST WITHIN IT ---
+    pub fn term_stats(&self) -> Vec<(String, Vec<DriverStats>)> {
+        let mut terms: HashMap<(i32, u8), HashMap<&str, DriverStats>> = HashMap::new();
+
+        for drive in self.drives.iter().filter(|d| d.leg == Leg::Outbound) {
+            let Some(day) = drive.day() else { continue };
+            let stats = terms.entry(term_of(day)).or_default()
+                .entry(drive.student_id.as_str())
+                .or_insert_with(|| DriverStats { name: drive.name.clone(), minibus: 0, car: 0, last_drove: String::new() });
+
+            if drive.minibus { stats.minibus += 1; } else { stats.car += 1; }
+            if drive.date > stats.last_drove { stats.last_drove = drive.date.clone(); }
+        }
+
+        let mut terms: Vec<_> = terms.into_iter().collect();
+        terms.sort_by_key(|(term, _)| std::cmp::Reverse(*term));
+        terms.into_iter()
+            .map(|(term, people)| {
+                let mut people: Vec<DriverStats> = people.into_values().collect();
+                people.sort_by(|a, b| b.total().cmp(&a.total()).then_with(|| a.name.cmp(&b.name)));
+                (term_label(term), people)
+            })
+            .collect()
+    }
+}
+
+// --- ACADEMIC TERMS: SPRING (JAN-MAR), SUMMER (APR-AUG) AND AUTUMN (SEP-DEC) ---
+// Keyed by (calendar year, order within the year) so they sort in date order
+fn term_of(day: NaiveDate) -> (i32, u8) {
+    match day.month() {
+        1..=3 => (day.year(), 0),
+        4..=8 => (day.year(), 1),
+        _     => (day.year(), 2),
+    }
+}
+
+fn term_label((year, term): (i32, u8)) -> String {
+    let name = match term { 0 => "Spring", 1 => "Summer", _ => "Autumn" };
+    format!("{} {}", name, year)
+}
+
+// --- FAIR ROTA: HANDS EACH VEHICLE TO WHOEVER ON BOARD HAS GONE LONGEST WITHOUT DRIVING ---
+// Only people who could drive it take over (and for a car, only owners who've opted in). Locked drivers and anyone who
+// must drive stay put. Returns a note for every change; the routes need planning again afterwards.
+pub fn rotate_drivers(groups: &mut [TransportGroup], history: &DriverHistory, session: &Session) -> Vec<String> {
+    let mut pinned: HashSet<&str> = session.locks.iter().filter(|l| l.driving).map(|l| l.student_id.as_str()).collect();
+    pinned.extend(session.constraints.iter().filter_map(|c| if let Constraint::MustDrive(id) = c { Some(id.as_str()) } else { None }));
+    let on        = session.outing_date();
+    let mut notes = Vec::new();
+
+    for g_idx in 0..groups.len() {
+        let group = &groups[g_idx];
+        if group.leg != Leg::Outbound || pinned.contains(group.driver.student_id.as_str()) { continue; }
+
+        // Never driven sorts before any date, so newcomers to the rota go first
+        let current   = history.last_drove(&group.driver.student_id);
+        let candidate = group.passengers.iter().enumerate()
+            .filter(|(_, p)| !pinned.contains(p.student_id.as_str()))
+            .filter(|(_, p)| group.is_minibus() || session.wants_to_drive(p))
+            .filter(|(_, p)| check_can_drive(p, group, group.passengers.len(), on).is_ok())
+            .map(|(p_idx, p)| (history.last_drove(&p.student_id), p_idx))
+            .filter(|(last, _)| *last < current)
+            .min();
+
+        let Some((last, p_idx)) = candidate else { continue };
+        let old_driver = group.driver.name.clone();
+        if make_driver(groups, g_idx, p_idx, on).is_ok() {
+            let group = &groups[g_idx];
+            let since = |name: &str, day: Option<NaiveDate>| match day {
+                Some(day) => format!("{} last drove {}", name, day),
+                None      => format!("{} hasn't driven yet", name),
+            };
+            notes.push(format!(
+                "Fair rota: {} drives {} instead of {} ({}, {}).",
+                group.driver.name, group.vehicle_label, old_driver, since(&group.driver.name, last), since(&old_driver, current),
+            ));
+        }
+    }
+    notes
+}
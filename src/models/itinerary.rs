@@ -0,0 +1,104 @@
+use super::{TransportGroup, Leg, Venue, Person};
+use super::edit::TransportPlan;
+use super::venue::venue_label;
+
+// --- ONE PERSON'S TRAVEL FOR THE DAY, TAKEN FROM THE SAME PLAN AS THE TRANSPORT SHEET ---
+#[derive(Debug, Clone)]
+pub struct Itinerary {
+    pub name: String,
+    pub student_id: String,
+    pub steps: Vec<String>,                 // The way there, then the way home (or why there isn't one)
+}
+
+impl Itinerary {
+    // --- EVERYTHING ON ONE LINE, FOR PASTING INTO A MESSAGE ---
+    pub fn line(&self) -> String {
+        format!("{}: {}", self.name, self.steps.join(" "))
+    }
+}
+
+// --- AN ITINERARY FOR EVERYONE IN THE PLAN, IN NAME ORDER ---
+// Drivers and passengers get both legs, and anyone making their own way or still without a seat is told so
+pub fn itineraries(plan: &TransportPlan, venues: &[Venue]) -> Vec<Itinerary> {
+    let mut itineraries: Vec<Itinerary> = Vec::new();
+    let mut add = |person: &Person, step: String| {
+        match itineraries.iter_mut().find(|i| i.student_id == person.student_id) {
+            Some(itinerary) => itinerary.steps.push(step),
+            None => itineraries.push(Itinerary { name: person.name.clone(), student_id: person.student_id.clone(), steps: vec![step] }),
+        }
+    };
+
+    // Outbound vehicles come before the journeys home in the plan, so the steps land in the order they're travelled
+    for group in &plan.groups {
+        add(&group.driver, driver_step(group, venues));
+        for p in &group.passengers {
+            add(p, passenger_step(group, p, venues));
+        }
+    }
+    for shortfall in &plan.needs_transport {
+        for p in &shortfall.people {
+            add(p, format!("No seat yet for the {} to {} - you'll get a message once one is sorted.", shortfall.departure_time, shortfall.venue_name));
+        }
+    }
+    for own in &plan.making_own_way {
+        add(&own.person, format!("Making your own way to {} for {}.", own.venue_name, own.departure_time));
+    }
+
+    // Nobody is left at the venue without being told
+    let home_planned = |id: &str| plan.groups.iter()
+        .filter(|g| g.leg == Leg::Return)
+        .any(|g| g.driver.student_id == id || g.passengers.iter().any(|p| p.student_id == id));
+    for itinerary in &mut itineraries {
+        if plan.outbound_vehicle(&itinerary.student_id).is_some() && !home_planned(&itinerary.student_id) {
+            itinerary.steps.push("No lift home planned yet.".to_string());
+        }
+    }
+
+    itineraries.sort_by(|a, b| a.name.cmp(&b.name));
+    itineraries
+}
+
+// --- THE STOP A PERSON GETS IN (OR OUT) AT, AND THE VEHICLE'S TIME THERE ---
+pub fn stop_for<'a>(group: &'a TransportGroup, person: &Person) -> Option<(&'a str, &'a str)> {
+    group.stops.iter()
+        .find(|s| s.people.contains(&person.name))
+        .map(|s| (s.location.as_str(), s.time.as_str()))
+}
+
+fn driver_step(group: &TransportGroup, venues: &[Venue]) -> String {
+    let venue = venue_label(venues, &group.destination);
+    match group.leg {
+        Leg::Outbound => {
+            let (start, time) = group.stops.first()
+                .map_or((group.pickup_location.as_str(), group.departure_time.as_str()), |s| (s.location.as_str(), s.time.as_str()));
+            format!("Driving {} from {} at {} to {}{}.", group.vehicle_label, start, time, venue, arriving(group))
+        }
+        Leg::Return => format!(
+            "Driving {} back from {} at {}, finishing at {}{}.",
+            group.vehicle_label, venue, group.departure_time, group.pickup_location, about(&group.arrival_time),
+        ),
+    }
+}
+
+fn passenger_step(group: &TransportGroup, person: &Person, venues: &[Venue]) -> String {
+    let venue      = venue_label(venues, &group.destination);
+    let (at, time) = stop_for(group, person).unwrap_or((group.pickup_location.as_str(), group.departure_time.as_str()));
+    match group.leg {
+        Leg::Outbound => format!(
+            "Picked up at {} at {} in {} (driver {}) to {}{}.",
+            at, time, group.vehicle_label, group.driver.name, venue, arriving(group),
+        ),
+        Leg::Return => format!(
+            "Home from {} at {} in {} (driver {}), dropped at {}{}.",
+            venue, group.departure_time, group.vehicle_label, group.driver.name, at, about(time),
+        ),
+    }
+}
+
+fn arriving(group: &TransportGroup) -> String {
+    if group.arrival_time.is_empty() { String::new() } else { format!(", arriving {}", group.arrival_time) }
+}
+
+fn about(time: &str) -> String {
+    if time.is_empty() { String::new() } else { format!(" about {}", time) }
+}
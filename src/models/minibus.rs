@@ -1,7 +1,18 @@
 use serde::{Serialize, Deserialize};
+use super::equipment::Haulage;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Minibus {
     pub registration: String,
     pub seats: u8,
-}
\ No newline at end of file
+    #[serde(default = "Minibus::default_depot")]
+    pub depot: String,                      // Pickup point the minibus is kept at (and collects its passengers from)
+    #[serde(flatten)]
+    pub kit: Haulage,                       // Tow bar and boot space
+}
+
+impl Minibus {
+    pub fn default_depot() -> String {
+        "Pleasance".to_string()
+    }
+}
@@ -2,37 +2,43 @@ pub mod person;
 pub mod car;
 pub mod minibus;
 pub mod allocations;
+pub mod boat;
+pub mod session;
+pub mod venue;
+pub mod pickup;
+pub mod route;
+pub mod strategy;
+pub mod solver;
+pub mod squad_strategies;
+pub mod fleet;
+pub mod returns;
+pub mod report;
+pub mod edit;
+pub mod constraints;
+pub mod equipment;
+pub mod credentials;
+pub mod history;
+pub mod expenses;
+pub mod itinerary;
+pub mod theme;
 
 pub use person::{Person, Gender};
 pub use car::Car;
 pub use minibus::Minibus;
-pub use allocations::{Allocation, TransportGroup};
+pub use allocations::{Allocation, TransportGroup, Leg};
+pub use boat::{Boat, BoatType};
+pub use session::{Session, Lock, SupportStaff, SupportRole};
+pub use venue::Venue;
+pub use pickup::PickupPoint;
+pub use route::{PickupStop, TravelMatrix, TravelTime};
+pub use strategy::StrategyKind;
+pub use fleet::FleetSchedule;
+pub use report::AllocationReport;
 
 use serde::{Serialize, Deserialize};
 
-// --- CENTRAL DESTINATION ENUM ---
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
-pub enum Destination {
-    StrathclydePark,
-    Auchenstarry,
-}
-
-// --- DESTINATION LOOKUP DATA ---
-impl Destination {
-
-    // Defining location venue labels for transport sheet
-    pub fn label(&self) -> &'static str {
-        match self {
-            Destination::StrathclydePark => "Strathclyde Park",
-            Destination::Auchenstarry => "Auchenstarry",
-        }
-    }
-
-    // Defining destination specific colours for transport sheet
-    pub fn color_rgb(&self) -> (f32, f32, f32) {
-        match self {
-            Destination::StrathclydePark => (1.0, 0.9, 0.8),
-            Destination::Auchenstarry => (0.9, 0.8, 0.9),
-        }
-    }
-}
\ No newline at end of file
+// --- CENTRAL DESTINATION KEY (THE ID OF A VENUE IN venues.json) ---
+// Serialised as a plain string so sheets saved when this was an enum ("StrathclydePark", "Auchenstarry") still load
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(transparent)]
+pub struct Destination(pub String);
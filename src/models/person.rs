@@ -1,5 +1,7 @@
 use serde::{Serialize, Deserialize};
 use super::car::Car;
+use chrono::NaiveDate;
+use super::credentials::DriverCredentials;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Gender {
@@ -14,6 +16,31 @@ pub struct Person {
     pub student_id: String,
     pub year_of_entry: u16,
     pub pickup_locations: Vec<String>,
+    #[serde(default)]
+    pub phone: String,                      // Mobile number printed on driver slips (blank if not given)
     pub car: Option<Car>,
-    pub can_drive_minibus: bool,
+    #[serde(default)]
+    pub credentials: DriverCredentials,     // Licence categories, assessment and insurance (checked against the day of the outing)
+    #[serde(default)]
+    pub wants_to_drive: bool,               // Standing opt-in to drive their own car (can be overridden per session)
+}
+
+impl Person {
+    pub fn can_drive_minibus(&self, on: NaiveDate) -> bool {
+        self.credentials.minibus_refusal(on).is_none()
+    }
+
+    pub fn can_drive_car(&self, on: NaiveDate) -> bool {
+        self.car.is_some() && self.credentials.car_refusal(on).is_none()
+    }
+
+    // Anything about their licence, assessment or insurance worth flagging in the app for the day of the outing
+    pub fn driving_warnings(&self, on: NaiveDate) -> Vec<String> {
+        self.credentials.warnings(on, self.credentials.claims_minibus(), self.car.is_some())
+    }
+
+    // Whether their licence lets them tow a trailer behind a minibus, or behind their car
+    pub fn can_tow(&self, minibus: bool) -> bool {
+        self.credentials.can_tow(minibus)
+    }
 }
\ No newline at end of file
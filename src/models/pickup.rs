@@ -0,0 +1,19 @@
+use serde::{Serialize, Deserialize};
+
+// --- A MANAGED PICKUP POINT THAT PEOPLE AND MINIBUS DEPOTS MUST REFER TO BY NAME (LOADED FROM pickup_points.json) ---
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PickupPoint {
+    pub name: String,                       // Name used on people, minibus depots and the transport sheet (e.g. "Library")
+    #[serde(default)]
+    pub latitude: f64,
+    #[serde(default)]
+    pub longitude: f64,
+    #[serde(default)]
+    pub notes: String,                      // Where exactly to wait (e.g. "George Square side entrance")
+}
+
+impl PickupPoint {
+    pub fn named(name: &str) -> Self {
+        PickupPoint { name: name.to_string(), latitude: 0.0, longitude: 0.0, notes: String::new() }
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+use std::fmt;
+use super::{Person, Minibus, Destination, TravelMatrix, TransportGroup, FleetSchedule};
+use super::venue::venue_label;
+use super::credentials::Licensed;
+
+// --- WHY A DEPARTURE COULDN'T SEAT EVERYONE ---
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShortfallReason {
+    NoLicensedMinibusDriver,                // A minibus was free, but nobody left over can drive one
+    FleetRanOut,                            // Every minibus was already out at the time
+    NoCarOwner,                             // Nobody left over has a car to take them
+    CapacityShort(usize),                   // The vehicles found were full, this many seats short
+}
+
+impl fmt::Display for ShortfallReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShortfallReason::NoLicensedMinibusDriver => write!(f, "no licensed minibus driver in the group"),
+            ShortfallReason::FleetRanOut => write!(f, "the minibus fleet ran out"),
+            ShortfallReason::NoCarOwner => write!(f, "no car owner left to drive"),
+            ShortfallReason::CapacityShort(seats) => write!(f, "capacity short by {} seat(s)", seats),
+        }
+    }
+}
+
+// --- THE PEOPLE LEFT OVER FROM ONE DEPARTURE, WHY, AND WHAT WOULD FIX IT ---
+#[derive(Debug, Clone)]
+pub struct Shortfall {
+    pub destination: Destination,
+    pub departure_time: String,
+    pub venue_name: String,                 // Kept so the report reads well without the venue list to hand
+    pub people: Vec<Person>,
+    pub reason: ShortfallReason,
+    pub suggestions: Vec<String>,
+}
+
+// --- RETURNED INSTEAD OF THE TRANSPORT GROUPS WHEN SOMEONE COULDN'T BE SEATED ---
+#[derive(Debug, Clone, Default)]
+pub struct AllocationReport {
+    pub shortfalls: Vec<Shortfall>,
+    pub partial: Vec<TransportGroup>,       // Everyone who could be seated, so the plan can still be published as it is
+}
+
+impl AllocationReport {
+    pub fn unallocated_count(&self) -> usize {
+        self.shortfalls.iter().map(|s| s.people.len()).sum()
+    }
+
+    // --- WORKS OUT WHY EACH DEPARTURE'S LEFT-OVER PEOPLE COULDN'T BE SEATED ---
+    pub fn diagnose(
+        groups: &[TransportGroup],
+        requests: &[(Destination, String, Vec<Person>)],
+        allocated_ids: &HashSet<String>,
+        minibuses: &[Minibus],
+        matrix: &TravelMatrix,
+        licensed: &Licensed,
+    ) -> Self {
+
+        // Rebuilding the minibus bookings from what was actually allocated
+        let mut schedule = FleetSchedule::new(minibuses);
+        for g in groups.iter().filter(|g| g.is_minibus()) {
+            if let Some(mb) = minibuses.iter().find(|m| m.registration == g.registration) {
+                schedule.book(mb, &g.driver.name, &g.destination, &g.departure_time, matrix);
+            }
+        }
+
+        let mut shortfalls = Vec::new();
+        for (dest, time, people) in requests {
+            let left_over: Vec<Person> = people.iter().filter(|p| !allocated_ids.contains(&p.student_id)).cloned().collect();
+            if left_over.is_empty() { continue; }
+
+            let venue_name   = venue_label(matrix.venues, dest).to_string();
+            let free_minibus = schedule.first_free(dest, time, matrix);
+            let licensed     = left_over.iter().any(|p| licensed.minibus(p));
+            let owners       = left_over.iter().any(|p| p.car.is_some());
+            let short        = left_over.len();
+
+            let reason = if free_minibus.is_some() && short >= 2 && !licensed {
+                ShortfallReason::NoLicensedMinibusDriver
+            } else if free_minibus.is_none() && !minibuses.is_empty() && short >= 2 && !owners {
+                ShortfallReason::FleetRanOut
+            } else if !owners {
+                ShortfallReason::NoCarOwner
+            } else {
+                ShortfallReason::CapacityShort(short)
+            };
+
+            // --- SUGGESTED FIXES ---
+            let mut suggestions = Vec::new();
+            if let Some(mb) = &free_minibus
+                && !licensed
+                && short >= 2
+            {
+                suggestions.push(format!(
+                    "Seating a licensed minibus driver in a {} {} crew would let minibus {} ({} seats) take them.",
+                    time, venue_name, mb.registration, mb.seats,
+                ));
+            }
+            if free_minibus.is_none() && !minibuses.is_empty() {
+                let (out, back) = FleetSchedule::estimate(&schedule.minibuses[0], dest, time, matrix);
+                if let Some(first_back) = schedule.bookings.iter().filter(|b| b.overlaps(out, back)).min_by_key(|b| b.back) {
+                    suggestions.push(format!(
+                        "Every minibus is out at {} - the first back is {} at {}.",
+                        time, first_back.registration, first_back.back.format("%H:%M"),
+                    ));
+                }
+            }
+
+            // A passenger at this departure with a big enough car frees their own seat and brings the rest
+            let spare_car_owner = groups.iter()
+                .filter(|g| &g.destination == dest && &g.departure_time == time)
+                .flat_map(|g| &g.passengers)
+                .filter(|p| p.car.as_ref().is_some_and(|c| c.seats as usize >= short))
+                .max_by_key(|p| p.car.as_ref().unwrap().seats);
+            match spare_car_owner {
+                Some(owner) => {
+                    let car = owner.car.as_ref().unwrap();
+                    suggestions.push(format!(
+                        "One more car-owner opt-in at {} {} would cover this (e.g. {} driving their {}, {} seats).",
+                        time, venue_name, owner.name, car.vehicle_type, car.seats,
+                    ));
+                }
+                None if short <= Self::TYPICAL_CAR_PASSENGERS => {
+                    suggestions.push(format!("One more car-owner opt-in at {} {} would cover this.", time, venue_name));
+                }
+                None => {
+                    suggestions.push(format!(
+                        "{} more car-owner opt-ins at {} {} would cover this.",
+                        short.div_ceil(Self::TYPICAL_CAR_PASSENGERS), time, venue_name,
+                    ));
+                }
+            }
+
+            shortfalls.push(Shortfall {
+                destination: dest.clone(),
+                departure_time: time.clone(),
+                venue_name,
+                people: left_over,
+                reason,
+                suggestions,
+            });
+        }
+
+        AllocationReport { shortfalls, partial: Vec::new() }
+    }
+
+    const TYPICAL_CAR_PASSENGERS: usize = 4;    // A five seat car with its driver
+}
+
+impl fmt::Display for AllocationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error: Not everyone could be assigned -")?;
+        for s in &self.shortfalls {
+            let names: Vec<&str> = s.people.iter().map(|p| p.name.as_str()).collect();
+            write!(f, "\n{} {}: {} left over ({})", s.departure_time, s.venue_name, s.people.len(), s.reason)?;
+            write!(f, "\n  {}", names.join(", "))?;
+            for suggestion in &s.suggestions {
+                write!(f, "\n  Suggestion: {}", suggestion)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AllocationReport {}
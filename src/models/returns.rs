@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::cmp::Reverse;
+use chrono::{NaiveTime, Duration};
+use super::{Person, Boat, Session, TravelMatrix, TransportGroup, Leg, Venue, FleetSchedule};
+use super::venue::find_venue;
+use super::route::plan_return_route;
+
+// --- ADDS THE JOURNEYS HOME TO A SET OF OUTBOUND VEHICLES, RETURNING NOTES ON ANYONE STRANDED AND ANY CLASHES ---
+pub fn plan_journeys_home(groups: &mut Vec<TransportGroup>, session: &Session, matrix: &TravelMatrix) -> Vec<String> {
+    let mut notes = Vec::new();
+
+    // Return vehicles are planned from the outbound ones, so every strategy gets the same journeys home
+    let (returns, stranded) = plan_returns(groups, session, matrix);
+    groups.extend(returns);
+    if !stranded.is_empty() {
+        notes.push(format!("Stranded after the session (no vehicle leaves once they're done): {}", stranded.join(", ")));
+    }
+
+    // The windows the allocator booked were estimates, so the planned routes are checked for real clashes
+    notes.extend(FleetSchedule::from_groups(groups, matrix).conflicts());
+    notes
+}
+
+// --- PLANS THE JOURNEYS HOME FROM THE OUTBOUND VEHICLES ---
+// Every driver takes their own vehicle back once their crew is done, and everyone else gets the first vehicle leaving
+// their venue after they are ready, dropping them at their boat's drop-off point (or wherever they got in).
+// Returns the return groups and the names of anyone left with no way home.
+pub fn plan_returns(outbound: &[TransportGroup], session: &Session, matrix: &TravelMatrix) -> (Vec<TransportGroup>, Vec<String>) {
+    let crews    = session.crew_index();
+    let boat_for = |p: &Person| crews.get(&p.student_id).map(|&b| &session.boats[b]);
+
+    // --- ONE RETURN VEHICLE PER OUTBOUND VEHICLE, LEAVING WHEN ITS DRIVER IS READY ---
+    let mut returns: Vec<TransportGroup> = Vec::new();
+    let mut leaves: Vec<NaiveTime>       = Vec::new();
+    for g in outbound.iter().filter(|g| g.leg == Leg::Outbound) {
+        let ready = ready_at(g, boat_for(&g.driver), matrix);
+        let end   = if g.is_minibus() { g.pickup_location.clone() } else { drop_off_for(&g.driver, g, boat_for(&g.driver)) };
+
+        returns.push(TransportGroup {
+            passengers: Vec::new(),
+            pickup_location: end,                               // Where the vehicle finishes (its depot or its driver's drop-off)
+            departure_time: ready.format("%H:%M").to_string(),
+            stops: Vec::new(),
+            arrival_time: String::new(),
+            leg: Leg::Return,
+            ..g.clone()
+        });
+        leaves.push(ready);
+    }
+
+    // --- EVERYONE ELSE, EARLIEST FINISHERS FIRST ---
+    let mut waiting: Vec<(NaiveTime, &Person, &TransportGroup)> = outbound.iter()
+        .filter(|g| g.leg == Leg::Outbound)
+        .flat_map(|g| g.passengers.iter().map(move |p| (p, g)))
+        .map(|(p, g)| (ready_at(g, boat_for(p), matrix), p, g))
+        .collect();
+    waiting.sort_by_key(|(ready, _, _)| *ready);
+
+    let mut drop_offs: HashMap<String, String> = HashMap::new();
+    let mut stranded: Vec<String>              = Vec::new();
+    for (ready, p, g) in waiting {
+        let drop_off = drop_off_for(p, g, boat_for(p));
+
+        // The first vehicle to leave once they're ready, preferring one already going their way
+        let target = returns.iter().enumerate()
+            .filter(|(r_idx, r)| r.destination == g.destination && leaves[*r_idx] >= ready && r.free_seats() > 0)
+            .min_by_key(|(r_idx, r)| {
+                let going_their_way = r.pickup_location == drop_off
+                    || r.passengers.iter().any(|q| drop_offs.get(&q.student_id) == Some(&drop_off));
+                (leaves[*r_idx], !going_their_way, Reverse(r.free_seats()))
+            })
+            .map(|(i, _)| i);
+
+        match target {
+            Some(r_idx) => {
+                returns[r_idx].passengers.push(p.clone());
+                drop_offs.insert(p.student_id.clone(), drop_off);
+            }
+            None => stranded.push(p.name.clone()),
+        }
+    }
+
+    // --- ROUTE PLANNING (ORDERED DROP-OFFS AND TIMES) ---
+    for r in &mut returns {
+        let stops: Vec<(String, String)> = r.passengers.iter().map(|p| (p.name.clone(), drop_offs[&p.student_id].clone())).collect();
+        let (stops, arrival) = plan_return_route(&r.destination, &r.pickup_location, &r.driver, &stops, &r.departure_time, matrix);
+        r.stops        = stops;
+        r.arrival_time = arrival;
+    }
+
+    returns.sort_by_key(|r| NaiveTime::parse_from_str(&r.departure_time, "%H:%M").ok());
+    (returns, stranded)
+}
+
+// --- WHEN A PERSON IS READY TO LEAVE THE VENUE (THEIR BOAT'S RETURN TIME, OR ARRIVAL PLUS THE VENUE'S USUAL SESSION) ---
+fn ready_at(group: &TransportGroup, boat: Option<&Boat>, matrix: &TravelMatrix) -> NaiveTime {
+    let parse = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").ok();
+
+    if let Some(time) = boat.and_then(|b| parse(&b.return_time)) {
+        return time;
+    }
+    let stay = find_venue(matrix.venues, &group.destination).map(|v| v.session_minutes).unwrap_or(Venue::default_session_minutes());
+    parse(&group.arrival_time)
+        .or_else(|| parse(&group.departure_time))
+        .map(|t| t + Duration::minutes(stay as i64))
+        .unwrap_or(NaiveTime::MIN)                              // No times at all - everyone is treated as ready together
+}
+
+// --- WHERE A PERSON WANTS DROPPING (THEIR BOAT'S DROP-OFF, OR THE STOP THEY GOT IN AT) ---
+fn drop_off_for(person: &Person, group: &TransportGroup, boat: Option<&Boat>) -> String {
+    if let Some(drop_off) = boat.and_then(|b| b.drop_off.clone()) {
+        return drop_off;
+    }
+    group.stops.iter()
+        .find(|s| s.people.contains(&person.name))
+        .map(|s| s.location.clone())
+        .or_else(|| person.pickup_locations.first().cloned())
+        .unwrap_or_else(|| group.pickup_location.clone())
+}
@@ -0,0 +1,237 @@
+use serde::{Serialize, Deserialize};
+use chrono::{NaiveTime, Duration};
+use super::{PickupPoint, Venue, Destination, Person};
+
+// --- A MEASURED DRIVE TIME BETWEEN TWO PLACES (ENTRIES IN travel_times.json, USED BOTH WAYS) ---
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TravelTime {
+    pub from: String,                       // Pickup point name or venue ID
+    pub to: String,
+    pub minutes: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub miles: Option<f64>,                 // Road distance, for mileage claims (estimated from coordinates if left out)
+}
+
+// --- ONE STOP ON A VEHICLE'S WAY TO THE VENUE ---
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PickupStop {
+    pub location: String,                   // Pickup point name
+    pub time: String,                       // When the vehicle leaves this stop (e.g. "06:40")
+    pub people: Vec<String>,                // Names of everyone (driver included) getting in here
+}
+
+// --- LOCAL DISTANCE MATRIX BETWEEN PICKUP POINTS AND VENUES ---
+// Measured times from travel_times.json win, then an estimate from coordinates, then a flat guess
+pub struct TravelMatrix<'a> {
+    pub pickup_points: &'a [PickupPoint],
+    pub venues: &'a [Venue],
+    pub travel_times: &'a [TravelTime],
+}
+
+impl<'a> TravelMatrix<'a> {
+    const DEFAULT_HOP_MINUTES: u32 = 10;    // Between two pickup points with no coordinates or measured time
+    const ROAD_FACTOR: f64         = 1.3;   // Roads are never straight lines
+    const AVERAGE_KMH: f64         = 30.0;  // City driving speed used for coordinate estimates
+    const KM_PER_MILE: f64         = 1.609_344;
+
+    // --- MINUTES BETWEEN TWO PICKUP POINTS ---
+    pub fn between(&self, from: &str, to: &str) -> u32 {
+        if from == to { return 0; }
+
+        if let Some(minutes) = self.measured(from, to) { return minutes; }
+
+        let point = |name: &str| self.pickup_points.iter().find(|p| p.name == name).and_then(|p| Self::coords(p.latitude, p.longitude));
+        match (point(from), point(to)) {
+            (Some(a), Some(b)) => Self::estimate(a, b),
+            _ => Self::DEFAULT_HOP_MINUTES,
+        }
+    }
+
+    // --- MINUTES FROM A PICKUP POINT TO A VENUE ---
+    pub fn to_venue(&self, from: &str, dest: &Destination) -> u32 {
+        if let Some(minutes) = self.measured(from, &dest.0) { return minutes; }
+
+        let Some(venue) = self.venues.iter().find(|v| &v.id == dest) else { return 0 };
+        let point = self.pickup_points.iter().find(|p| p.name == from).and_then(|p| Self::coords(p.latitude, p.longitude));
+        match (point, Self::coords(venue.latitude, venue.longitude)) {
+            (Some(a), Some(b)) => Self::estimate(a, b),
+            _ => venue.travel_minutes,      // The venue's typical travel time from the boathouse
+        }
+    }
+
+    // --- ROAD MILES BETWEEN TWO PICKUP POINTS (FOR MILEAGE CLAIMS) ---
+    // Measured distances win, then an estimate from coordinates, then the drive time at the average speed
+    pub fn miles_between(&self, from: &str, to: &str) -> f64 {
+        if from == to { return 0.0; }
+
+        if let Some(miles) = self.measured_miles(from, to) { return miles; }
+
+        let point = |name: &str| self.pickup_points.iter().find(|p| p.name == name).and_then(|p| Self::coords(p.latitude, p.longitude));
+        match (point(from), point(to)) {
+            (Some(a), Some(b)) => Self::road_km(a, b) / Self::KM_PER_MILE,
+            _ => Self::miles_in(self.between(from, to)),
+        }
+    }
+
+    // --- ROAD MILES FROM A PICKUP POINT TO A VENUE ---
+    pub fn miles_to_venue(&self, from: &str, dest: &Destination) -> f64 {
+        if let Some(miles) = self.measured_miles(from, &dest.0) { return miles; }
+
+        let venue = self.venues.iter().find(|v| &v.id == dest);
+        let point = self.pickup_points.iter().find(|p| p.name == from).and_then(|p| Self::coords(p.latitude, p.longitude));
+        match (point, venue.and_then(|v| Self::coords(v.latitude, v.longitude))) {
+            (Some(a), Some(b)) => Self::road_km(a, b) / Self::KM_PER_MILE,
+            _ => Self::miles_in(self.to_venue(from, dest)),
+        }
+    }
+
+    fn find_measured(&self, from: &str, to: &str) -> Option<&TravelTime> {
+        self.travel_times.iter().find(|t| (t.from == from && t.to == to) || (t.from == to && t.to == from))
+    }
+
+    fn measured(&self, from: &str, to: &str) -> Option<u32> {
+        self.find_measured(from, to).map(|t| t.minutes)
+    }
+
+    fn measured_miles(&self, from: &str, to: &str) -> Option<f64> {
+        self.find_measured(from, to).and_then(|t| t.miles)
+    }
+
+    // How far the average speed covers in that many minutes
+    fn miles_in(minutes: u32) -> f64 {
+        minutes as f64 / 60.0 * Self::AVERAGE_KMH / Self::KM_PER_MILE
+    }
+
+    // Treats (0, 0) as "no coordinates entered" since nobody rows in the Gulf of Guinea
+    fn coords(lat: f64, lon: f64) -> Option<(f64, f64)> {
+        if lat == 0.0 && lon == 0.0 { None } else { Some((lat, lon)) }
+    }
+
+    // Great circle distance stretched by the road factor
+    fn road_km((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+        let (dlat, dlon) = ((lat2 - lat1).to_radians(), (lon2 - lon1).to_radians());
+        let a  = (dlat / 2.0).sin().powi(2) + lat1.to_radians().cos() * lat2.to_radians().cos() * (dlon / 2.0).sin().powi(2);
+        6371.0 * 2.0 * a.sqrt().asin() * Self::ROAD_FACTOR
+    }
+
+    // The road distance driven at the average speed
+    fn estimate(a: (f64, f64), b: (f64, f64)) -> u32 {
+        ((Self::road_km(a, b) / Self::AVERAGE_KMH) * 60.0).ceil() as u32
+    }
+}
+
+// --- PLANS THE ORDERED PICKUP STOPS FOR ONE VEHICLE ---
+// The vehicle starts at `start`, visits the nearest unvisited stop each time and leaves the last stop at `departure_time`;
+// earlier stops are timed backwards from there. Returns the stops and the estimated arrival time at the venue.
+pub fn plan_route(
+    start: &str,
+    driver: &Person,
+    passengers: &[Person],
+    dest: &Destination,
+    departure_time: &str,
+    matrix: &TravelMatrix,
+) -> (Vec<PickupStop>, String) {
+
+    // Everyone gets in at a stop the vehicle is already making if they can (the start first), otherwise at their first listed pickup point
+    let mut stops: Vec<PickupStop> = vec![PickupStop { location: start.to_string(), time: String::new(), people: vec![driver.name.clone()] }];
+    for p in passengers {
+        let location = stops.iter()
+            .find(|s| p.pickup_locations.contains(&s.location))
+            .map(|s| s.location.clone())
+            .or_else(|| p.pickup_locations.first().cloned())
+            .unwrap_or_else(|| start.to_string());
+        match stops.iter_mut().find(|s| s.location == location) {
+            Some(stop) => stop.people.push(p.name.clone()),
+            None => stops.push(PickupStop { location, time: String::new(), people: vec![p.name.clone()] }),
+        }
+    }
+
+    // --- NEAREST NEIGHBOUR ORDERING FROM THE START ---
+    let mut ordered = vec![stops.remove(0)];
+    while !stops.is_empty() {
+        let here = &ordered.last().unwrap().location;
+        let next = stops.iter().enumerate()
+            .min_by_key(|(_, s)| matrix.between(here, &s.location))
+            .map(|(i, _)| i)
+            .unwrap();
+        ordered.push(stops.remove(next));
+    }
+
+    // --- TIMES (BACKWARDS FROM THE DEPARTURE AT THE LAST STOP) ---
+    let Ok(departure) = NaiveTime::parse_from_str(departure_time.trim(), "%H:%M") else {
+        return (ordered, String::new());                        // Unparseable times leave the stops untimed
+    };
+
+    let mut time = departure;
+    for i in (0..ordered.len()).rev() {
+        ordered[i].time = time.format("%H:%M").to_string();
+        if i > 0 {
+            time -= Duration::minutes(matrix.between(&ordered[i - 1].location, &ordered[i].location) as i64);
+        }
+    }
+
+    let last    = &ordered.last().unwrap().location;
+    let arrival = departure + Duration::minutes(matrix.to_venue(last, dest) as i64);
+    (ordered, arrival.format("%H:%M").to_string())
+}
+
+// --- PLANS THE ORDERED DROP-OFFS FOR ONE VEHICLE ON THE WAY BACK ---
+// The vehicle leaves the venue at `departure_time`, visits the nearest unvisited drop-off each time and finishes at `end`
+// (its depot, or its driver's own drop-off). Returns the stops and the estimated time it gets to `end`.
+pub fn plan_return_route(
+    venue: &Destination,
+    end: &str,
+    driver: &Person,
+    drop_offs: &[(String, String)],         // (Name, drop-off point) for each passenger
+    departure_time: &str,
+    matrix: &TravelMatrix,
+) -> (Vec<PickupStop>, String) {
+
+    let mut stops: Vec<PickupStop> = Vec::new();
+    for (name, location) in drop_offs {
+        match stops.iter_mut().find(|s| &s.location == location) {
+            Some(stop) => stop.people.push(name.clone()),
+            None => stops.push(PickupStop { location: location.clone(), time: String::new(), people: vec![name.clone()] }),
+        }
+    }
+
+    // The end is always the last stop, and the driver gets out there along with anyone else dropped at it
+    let mut last = match stops.iter().position(|s| s.location == end) {
+        Some(i) => stops.remove(i),
+        None => PickupStop { location: end.to_string(), time: String::new(), people: Vec::new() },
+    };
+    last.people.push(driver.name.clone());
+
+    // --- NEAREST NEIGHBOUR ORDERING FROM THE VENUE ---
+    let mut ordered: Vec<PickupStop> = Vec::new();
+    while !stops.is_empty() {
+        let next = stops.iter().enumerate()
+            .min_by_key(|(_, s)| match ordered.last() {
+                Some(here) => matrix.between(&here.location, &s.location),
+                None => matrix.to_venue(&s.location, venue),
+            })
+            .map(|(i, _)| i)
+            .unwrap();
+        ordered.push(stops.remove(next));
+    }
+    ordered.push(last);
+
+    // --- TIMES (FORWARDS FROM LEAVING THE VENUE) ---
+    let Ok(departure) = NaiveTime::parse_from_str(departure_time.trim(), "%H:%M") else {
+        return (ordered, String::new());
+    };
+
+    let mut time = departure;
+    let mut here: Option<String> = None;
+    for stop in &mut ordered {
+        let minutes = match &here {
+            Some(prev) => matrix.between(prev, &stop.location),
+            None => matrix.to_venue(&stop.location, venue),
+        };
+        time += Duration::minutes(minutes as i64);
+        stop.time = time.format("%H:%M").to_string();
+        here = Some(stop.location.clone());
+    }
+
+    (ordered, time.format("%H:%M").to_string())
+}
@@ -0,0 +1,118 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use chrono::NaiveDate;
+use super::boat::Boat;
+use super::person::Person;
+use super::constraints::Constraint;
+use super::equipment::EquipmentNeed;
+use super::credentials::today;
+use super::Destination;
+
+// --- A NAMED CREW PLAN (EVERYTHING ON THE LAKE) THAT CAN BE SAVED AND REOPENED LATER ---
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub name: String,                       // Display name (e.g. "Saturday 14th"), also used as the file name
+    #[serde(default)]
+    pub date: String,                       // Day of the outing (YYYY-MM-DD), blank for today
+    pub boats: Vec<Boat>,                   // Boats on the lake with their seats, times, destinations and positions
+    #[serde(default)]
+    pub driver_overrides: HashMap<String, bool>,    // Student ID -> "Wants to Drive" for this session only (replaces their standing default)
+    #[serde(default)]
+    pub locks: Vec<Lock>,                   // Vehicle choices made by hand in the Transport view, kept when the allocator is re-run
+    #[serde(default)]
+    pub constraints: Vec<Constraint>,       // Who rides together or apart, who must or mustn't drive, and who travels privately
+    #[serde(default)]
+    pub support: Vec<SupportStaff>,         // People off the water who still need a lift (coaches, launch drivers, spares, cox trainees)
+    #[serde(default)]
+    pub equipment: Vec<EquipmentNeed>,      // Trailers, blades, ergs and fuel to take on each departure
+}
+
+// --- A PERSON PINNED TO A VEHICLE (AS ITS DRIVER OR AS A PASSENGER) ---
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lock {
+    pub student_id: String,
+    pub registration: String,               // Minibus or car registration
+    pub driving: bool,
+}
+
+// --- WHAT SOMEONE IN THE SUPPORT STAFF TRAY IS COMING ALONG AS ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SupportRole {
+    #[default]
+    Coach,
+    LaunchDriver,
+    Spare,
+    CoxTrainee,
+}
+
+impl SupportRole {
+    pub const ALL: [SupportRole; 4] = [SupportRole::Coach, SupportRole::LaunchDriver, SupportRole::Spare, SupportRole::CoxTrainee];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SupportRole::Coach => "Coach",
+            SupportRole::LaunchDriver => "Launch driver",
+            SupportRole::Spare => "Spare",
+            SupportRole::CoxTrainee => "Cox trainee",
+        }
+    }
+}
+
+// --- SOMEONE NOT IN A BOAT WHO TRAVELS WITH A DEPARTURE LIKE A ROWER WOULD ---
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupportStaff {
+    pub student_id: String,
+    pub role: SupportRole,
+    pub destination: Option<Destination>,
+    pub departure_time: String,             // HH:MM, matched against the boats so they share vehicles with that departure
+    #[serde(default)]
+    pub prefers_driving: bool,              // Put forward as a driver before the rowers (their car, or a minibus if licensed)
+}
+
+impl SupportStaff {
+    pub fn new(student_id: String) -> Self {
+        SupportStaff {
+            student_id,
+            role: SupportRole::default(),
+            destination: None,
+            departure_time: String::new(),
+            prefers_driving: false,
+        }
+    }
+}
+
+impl Session {
+
+    // --- THE DAY THE CREWS GO OUT: DRIVERS ARE CHECKED AGAINST IT, AND SHEETS, EXPORTS AND THE HISTORY ARE DATED WITH IT ---
+    pub fn outing_date(&self) -> NaiveDate {
+        NaiveDate::parse_from_str(self.date.trim(), "%Y-%m-%d").unwrap_or_else(|_| today())
+    }
+
+    // --- CHECKS THE OUTING DATE CAN BE READ (BLANK IS FINE) ---
+    pub fn validate_date(&self) -> Result<(), String> {
+        if self.date.trim().is_empty() || NaiveDate::parse_from_str(self.date.trim(), "%Y-%m-%d").is_ok() {
+            return Ok(());
+        }
+        Err(format!("Outing date '{}' must be written as YYYY-MM-DD (e.g. 2025-09-01).", self.date))
+    }
+
+    // --- WHETHER A PERSON WANTS TO DRIVE THEIR OWN CAR IN THIS SESSION (OVERRIDE FIRST, THEN THEIR STANDING DEFAULT) ---
+    pub fn wants_to_drive(&self, person: &Person) -> bool {
+        self.driver_overrides.get(&person.student_id).copied().unwrap_or(person.wants_to_drive)
+    }
+
+    // --- WHICH BOAT (INDEX INTO `boats`) EACH SEATED PERSON IS IN, KEYED BY STUDENT ID ---
+    pub fn crew_index(&self) -> HashMap<String, usize> {
+        let mut crews = HashMap::new();
+        for (b_idx, boat) in self.boats.iter().enumerate() {
+            for id in boat.seats.iter().flatten() {
+                crews.insert(id.clone(), b_idx);
+            }
+        }
+        crews
+    }
+
+    pub fn lock_for(&self, student_id: &str) -> Option<&Lock> {
+        self.locks.iter().find(|l| l.student_id == student_id)
+    }
+}
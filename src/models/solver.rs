@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::cmp::Reverse;
+use super::{Person, Minibus, Destination, Session, TravelMatrix, TransportGroup, Allocation, FleetSchedule};
+use super::allocations::Greedy;
+use super::equipment::DepartureKit;
+use super::strategy::{AllocationStrategy, AllocationScore, AllocationOutcome};
+use super::credentials::Licensed;
+
+// --- BRANCH AND BOUND SOLVER THAT MINIMISES VEHICLES, THEN PICKUP MISMATCHES, THEN GENDER IMBALANCE ---
+// Vehicle choice is exact: every way of splitting the minibuses between departure groups is tried (memoised per group,
+// and a minibus can go to several groups as long as their time windows don't overlap),
+// and within a group the smallest set of cars that covers everyone is found by increasing set size. Seating the
+// passengers into the chosen vehicles is then improved by moves and swaps until nothing gets better.
+pub struct OptimalSolver;
+
+impl OptimalSolver {
+    const MAX_EXACT_MINIBUSES: usize = 10;  // Only the largest ten are considered (3^10 splits is still instant)
+    const MAX_SWAP_PASSES: usize     = 50;
+    const MISMATCH_WEIGHT: usize     = 1000;// A pickup mismatch always outweighs any amount of gender imbalance
+    const MAX_GROUPS: usize          = 64;  // One bit per departure group in the search state
+}
+
+// --- ONE DEPARTURE GROUP'S BEST PLAN FOR A GIVEN SET OF MINIBUSES ---
+#[derive(Clone)]
+struct GroupPlan {
+    groups: Vec<TransportGroup>,
+    score: AllocationScore,
+}
+
+// --- WHAT STAGE TWO TRIES TO LOWER FOR ONE VEHICLE ---
+fn cost(vehicle: &TransportGroup) -> usize {
+    AllocationScore::vehicle_mismatches(&vehicle.pickup_location, &vehicle.passengers) * OptimalSolver::MISMATCH_WEIGHT
+        + AllocationScore::vehicle_imbalance(&vehicle.driver, &vehicle.passengers)
+}
+
+impl AllocationStrategy for OptimalSolver {
+    fn allocate(
+        &self,
+        requests: Vec<(Destination, String, Vec<Person>)>,
+        minibuses: &[Minibus],
+        session: &Session,
+        matrix: &TravelMatrix,
+        licensed: &Licensed,
+    ) -> AllocationOutcome {
+
+        if requests.len() > Self::MAX_GROUPS {
+            let mut outcome = Greedy.allocate(requests, minibuses, session, matrix, licensed);
+            outcome.diagnostics.push(format!("More than {} departures, so the greedy pass was used instead.", Self::MAX_GROUPS));
+            return outcome;
+        }
+
+        // Largest minibuses first so any that don't fit in the exact search are the least useful ones
+        let mut fleet = minibuses.to_vec();
+        fleet.sort_by_key(|m| Reverse(m.seats));
+        fleet.truncate(Self::MAX_EXACT_MINIBUSES);
+        let left_out = minibuses.len() - fleet.len();
+
+        // For each minibus and group, the groups whose time window clashes with it (a group always clashes with itself)
+        let windows: Vec<Vec<_>> = requests.iter()
+            .map(|(dest, time, _)| fleet.iter().map(|mb| FleetSchedule::estimate(mb, dest, time, matrix)).collect())
+            .collect();
+        let clashes: Vec<Vec<u64>> = (0..requests.len()).map(|g| {
+            (0..fleet.len()).map(|i| {
+                let (out, back) = windows[g][i];
+                (0..requests.len())
+                    .filter(|&h| windows[h][i].0 < back && out < windows[h][i].1)
+                    .fold(0u64, |mask, h| mask | (1 << h))
+            }).collect()
+        }).collect();
+
+        let mut search = Search { requests: &requests, fleet: &fleet, session, licensed, clashes, plans: HashMap::new(), best: HashMap::new() };
+        let (_, masks) = search.best_from(0, vec![0; fleet.len()]);
+
+        // Rebuilding the winning plan for each group from the cache
+        let mut final_allocations = Vec::new();
+        for (g_idx, mask) in masks.into_iter().enumerate() {
+            if let Some(plan) = search.plan(g_idx, mask) {
+                final_allocations.extend(plan.groups);
+            }
+        }
+
+        let mut outcome = AllocationOutcome::new(Allocation::finish(final_allocations, &requests, minibuses, matrix, licensed));
+        if left_out > 0 {
+            outcome.diagnostics.push(format!("Only the {} largest minibuses were considered ({} left out).", fleet.len(), left_out));
+        }
+        outcome
+    }
+}
+
+// --- MEMOISED SEARCH OVER HOW THE MINIBUSES ARE SPLIT BETWEEN DEPARTURE GROUPS ---
+struct Search<'a> {
+    requests: &'a [(Destination, String, Vec<Person>)],
+    fleet: &'a [Minibus],
+    session: &'a Session,
+    licensed: &'a Licensed,
+    clashes: Vec<Vec<u64>>,                                         // [group][minibus] -> groups that minibus can't also serve
+    plans: HashMap<(usize, u32), Option<GroupPlan>>,                // (group, minibus mask) -> that group's plan
+    best: HashMap<(usize, Vec<u64>), (AllocationScore, Vec<u32>)>,  // (first group, groups each minibus is blocked for) -> best score and masks
+}
+
+impl Search<'_> {
+
+    // --- BEST TOTAL SCORE FOR GROUPS g.. GIVEN WHICH GROUPS EACH MINIBUS IS ALREADY BLOCKED FOR ---
+    fn best_from(&mut self, g: usize, mut blocked: Vec<u64>) -> (AllocationScore, Vec<u32>) {
+        if g == self.requests.len() {
+            return (AllocationScore::default(), Vec::new());
+        }
+
+        // Earlier groups are settled, so only the bits from g onwards matter (keeps the cache small)
+        let settled = (1u64 << g) - 1;
+        for b in &mut blocked {
+            *b &= !settled;
+        }
+        if let Some(known) = self.best.get(&(g, blocked.clone())) {
+            return known.clone();
+        }
+
+        let available = (0..self.fleet.len()).filter(|&i| blocked[i] & (1 << g) == 0).fold(0u32, |mask, i| mask | (1 << i));
+
+        let mut best: Option<(AllocationScore, Vec<u32>)> = None;
+        let mut sub = available;
+        loop {
+            // Every subset of the available minibuses (including none) is a candidate for this group
+            if let Some(plan) = self.plan(g, sub) {
+                let mut rest_blocked = blocked.clone();
+                for (i, b) in rest_blocked.iter_mut().enumerate() {
+                    if sub & (1 << i) != 0 {
+                        *b |= self.clashes[g][i];
+                    }
+                }
+                let (rest_score, rest_masks) = self.best_from(g + 1, rest_blocked);
+                let total = plan.score + rest_score;
+
+                if best.as_ref().is_none_or(|(b, _)| total < *b) {
+                    let mut masks = vec![sub];
+                    masks.extend(rest_masks);
+                    best = Some((total, masks));
+                }
+            }
+            if sub == 0 { break; }
+            sub = (sub - 1) & available;
+        }
+
+        // The empty subset is always feasible, so there is always an answer
+        let best = best.unwrap();
+        self.best.insert((g, blocked), best.clone());
+        best
+    }
+
+    fn plan(&mut self, g: usize, mask: u32) -> Option<GroupPlan> {
+        if let Some(known) = self.plans.get(&(g, mask)) {
+            return known.clone();
+        }
+        let minibuses: Vec<&Minibus> = (0..self.fleet.len()).filter(|i| mask & (1 << i) != 0).map(|i| &self.fleet[i]).collect();
+        let (dest, time, people) = &self.requests[g];
+        let plan = plan_group(people, &minibuses, dest, time, self.session, self.licensed);
+        self.plans.insert((g, mask), plan.clone());
+        plan
+    }
+}
+
+// --- STAGE ONE AND TWO FOR A SINGLE DEPARTURE GROUP USING EXACTLY THE GIVEN MINIBUSES ---
+// The departure's equipment counts towards what the vehicles must hold: cargo past their boots takes seats, and each
+// trailer needs a vehicle that can tow with a driver licensed to tow it (unless no choice of vehicles could manage that)
+fn plan_group(people: &[Person], minibuses: &[&Minibus], dest: &Destination, time: &str, session: &Session, licensed: &Licensed) -> Option<GroupPlan> {
+    let n = people.len();
+    if minibuses.len() > n { return None; }                         // Every minibus needs someone from the group to drive it
+
+    // Looked up once here, since the counts below run for every combination of cars
+    let can_drive_minibus: Vec<bool> = people.iter().map(|p| licensed.minibus(p)).collect();
+    let spare_drivers = can_drive_minibus.iter().filter(|c| **c).count().checked_sub(minibuses.len())?;   // None when the minibuses can't all be driven
+    let minibus_drivers = |car_drivers: &[usize]| (0..n)
+        .filter(|i| can_drive_minibus[*i] && !car_drivers.contains(i))
+        .count();
+    let tows_minibus: Vec<bool> = (0..n).map(|i| can_drive_minibus[i] && people[i].can_tow(true)).collect();
+    let tows_car: Vec<bool>     = people.iter().map(|p| p.can_tow(false) && p.car.as_ref().is_some_and(|c| c.kit.can_tow())).collect();
+    let towing_minibuses        = minibuses.iter().filter(|m| m.kit.can_tow()).count();
+    let towers = |cars: &[usize]| cars.iter().filter(|&&i| tows_car[i]).count()
+        + towing_minibuses.min((0..n).filter(|i| tows_minibus[*i] && !cars.contains(i)).count());
+
+    // Car owners, largest car first (and willing drivers first among equal cars)
+    let mut owners: Vec<usize> = (0..n).filter(|&i| people[i].car.is_some()).collect();
+    owners.sort_by_key(|&i| (Reverse(people[i].car.as_ref().unwrap().seats), !session.wants_to_drive(&people[i])));
+
+    let seats_of = |cars: &[usize]| -> usize {
+        minibuses.iter().map(|m| m.seats as usize).sum::<usize>()
+            + cars.iter().map(|&i| people[i].car.as_ref().unwrap().seats as usize).sum::<usize>()
+    };
+    let boots_of = |cars: &[usize]| -> usize {
+        minibuses.iter().map(|m| m.kit.cargo_slots as usize).sum::<usize>()
+            + cars.iter().map(|&i| people[i].car.as_ref().unwrap().kit.cargo_slots as usize).sum::<usize>()
+    };
+    let mut largest_boots: Vec<usize> = owners.iter().map(|&i| people[i].car.as_ref().unwrap().kit.cargo_slots as usize).collect();
+    largest_boots.sort_by_key(|&b| Reverse(b));
+
+    // What has to be carried, left out of the search when even every car together couldn't take it
+    let mut kit = DepartureKit::for_departure(&session.equipment, dest, time);
+    let all_tow = owners.iter().filter(|&&i| tows_car[i]).count() + towing_minibuses.min(tows_minibus.iter().filter(|t| **t).count());
+    let trailers = if all_tow >= kit.trailers { kit.trailers } else { 0 };
+    let cargo    = if seats_of(&owners) + boots_of(&owners) >= n + kit.cargo_slots() { kit.cargo_slots() } else { 0 };
+    let needed   = |cars: &[usize]| n + cargo.saturating_sub(boots_of(cars));
+
+    // --- STAGE ONE: THE SMALLEST SET OF CARS THAT SEATS EVERYONE AND CARRIES THE KIT ---
+    type CarSetRank = (Reverse<usize>, Reverse<usize>);     // (willing drivers, total seats), both most first
+    let mut chosen: Option<Vec<usize>> = None;
+    let most_cars = owners.iter().filter(|&&i| !can_drive_minibus[i]).count() + spare_drivers;
+    for k in 0..=owners.len().min(most_cars) {
+        // Even the k largest cars (and the k largest boots) can't seat everyone
+        if seats_of(&owners[..k]) < n || seats_of(&owners[..k]) + boots_of(&[]) + largest_boots[..k].iter().sum::<usize>() < n + cargo { continue; }
+
+        // Car sets that would take the drivers the minibuses need are never built
+        let mut best_k: Option<(Vec<usize>, CarSetRank)> = None;
+        for_each_combination(&owners, k, (&can_drive_minibus, spare_drivers), &mut |cars| {
+            if seats_of(cars) < needed(cars) { return; }
+            if trailers > 0 && towers(cars) < trailers { return; }
+
+            // Ties go to the set with the most willing drivers, then the most spare seats
+            let willing = cars.iter().filter(|&&i| session.wants_to_drive(&people[i])).count();
+            let rank    = (Reverse(willing), Reverse(seats_of(cars)));
+            if best_k.as_ref().is_none_or(|(_, r)| rank < *r) {
+                best_k = Some((cars.to_vec(), rank));
+            }
+        });
+        if let Some((cars, _)) = best_k {
+            chosen = Some(cars);
+            break;
+        }
+    }
+
+    // Not everyone can be seated - use every car (if that still leaves enough minibus drivers) and seat as many as possible
+    let cars = match chosen {
+        Some(cars) => cars,
+        None if minibus_drivers(&owners) >= minibuses.len() => owners.clone(),
+        None => return None,
+    };
+
+    // --- PICKING DRIVERS FOR THE CHOSEN VEHICLES ---
+    let mut taken: Vec<usize> = cars.clone();
+    let mut vehicles: Vec<TransportGroup> = Vec::new();
+    let mut trailers_left = trailers.saturating_sub(cars.iter().filter(|&&i| tows_car[i]).count());
+
+    for mb in minibuses {
+        // Prefer someone licensed to tow for a minibus that can tow a waiting trailer, then licensed drivers who live
+        // by the depot and haven't asked to drive their own car
+        let tows  = |i: usize| trailers_left > 0 && mb.kit.can_tow() && tows_minibus[i];
+        let d_idx = (0..n)
+            .filter(|i| can_drive_minibus[*i] && !taken.contains(i))
+            .max_by_key(|&i| (tows(i), people[i].pickup_locations.contains(&mb.depot), !session.wants_to_drive(&people[i])))?;
+        if tows(d_idx) { trailers_left -= 1; }
+        taken.push(d_idx);
+        vehicles.push(TransportGroup::minibus(people[d_idx].clone(), mb, dest, time));
+    }
+    for &c_idx in &cars {
+        vehicles.push(TransportGroup::car(people[c_idx].clone(), dest, time));
+    }
+
+    // The trailer and cargo go on before anyone is seated
+    kit.reserve(&mut vehicles);
+
+    // --- STAGE TWO: SEAT THE PASSENGERS, MOST CONSTRAINED FIRST, THEN IMPROVE ---
+    let mut passengers: Vec<&Person> = (0..n).filter(|i| !taken.contains(i)).map(|i| &people[i]).collect();
+    passengers.sort_by_key(|p| p.pickup_locations.len());
+
+    let mut unplaced = 0;
+    for p in passengers {
+        let target = vehicles.iter().enumerate()
+            .filter(|(_, v)| v.free_seats() > 0)
+            .max_by_key(|(_, v)| (p.pickup_locations.contains(&v.pickup_location), v.free_seats()))
+            .map(|(i, _)| i);
+
+        match target {
+            Some(v_idx) => vehicles[v_idx].passengers.push(p.clone()),
+            None => unplaced += 1,
+        }
+    }
+
+    improve(&mut vehicles);
+
+    let score = AllocationScore::of(&vehicles, unplaced);
+    Some(GroupPlan { groups: vehicles, score })
+}
+
+// --- LOCAL SEARCH: MOVE OR SWAP PASSENGERS BETWEEN VEHICLES WHILE IT LOWERS THE TOTAL COST ---
+fn improve(vehicles: &mut [TransportGroup]) {
+    for _ in 0..OptimalSolver::MAX_SWAP_PASSES {
+        let mut improved = false;
+
+        for a in 0..vehicles.len() {
+            for b in 0..vehicles.len() {
+                if a == b { continue; }
+
+                let mut i = 0;
+                while i < vehicles[a].passengers.len() {
+                    let before = cost(&vehicles[a]) + cost(&vehicles[b]);
+
+                    // Try moving passenger i from a into a free seat in b
+                    if vehicles[b].free_seats() > 0 {
+                        let p = vehicles[a].passengers.remove(i);
+                        vehicles[b].passengers.push(p);
+                        if cost(&vehicles[a]) + cost(&vehicles[b]) < before {
+                            improved = true;
+                            continue;                       // Index i now holds the next passenger
+                        }
+                        let p = vehicles[b].passengers.pop().unwrap();
+                        vehicles[a].passengers.insert(i, p);
+                    }
+
+                    // Try swapping passenger i with each passenger in b
+                    for j in 0..vehicles[b].passengers.len() {
+                        let before = cost(&vehicles[a]) + cost(&vehicles[b]);
+                        swap_between(vehicles, a, i, b, j);
+                        if cost(&vehicles[a]) + cost(&vehicles[b]) < before {
+                            improved = true;
+                        } else {
+                            swap_between(vehicles, a, i, b, j);
+                        }
+                    }
+                    i += 1;
+                }
+            }
+        }
+
+        if !improved { break; }
+    }
+}
+
+fn swap_between(vehicles: &mut [TransportGroup], a: usize, i: usize, b: usize, j: usize) {
+    let from_a = vehicles[a].passengers[i].clone();
+    let from_b = std::mem::replace(&mut vehicles[b].passengers[j], from_a);
+    vehicles[a].passengers[i] = from_b;
+}
+
+// --- CALLS `f` WITH EVERY k-SIZED COMBINATION OF `items` (IN ORDER) ---
+// holding at most `limit` of the items marked in `limited`, so a branch is dropped as soon as it goes over
+fn for_each_combination(items: &[usize], k: usize, (limited, limit): (&[bool], usize), f: &mut dyn FnMut(&[usize])) {
+    fn recurse(items: &[usize], k: usize, start: usize, current: &mut Vec<usize>, used: usize, limits: (&[bool], usize), f: &mut dyn FnMut(&[usize])) {
+        if current.len() == k {
+            f(current);
+            return;
+        }
+        let (limited, limit) = limits;
+        for i in start..items.len() {
+            if items.len() - i < k - current.len() { break; }   // Not enough items left to finish the combination
+            let used = used + limited[items[i]] as usize;
+            if used > limit { continue; }
+            current.push(items[i]);
+            recurse(items, k, i + 1, current, used, limits, f);
+            current.pop();
+        }
+    }
+    recurse(items, k, 0, &mut Vec::with_capacity(k), 0, (limited, limit), f);
+}
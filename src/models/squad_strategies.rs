@@ -0,0 +1,290 @@
+use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use super::{Person, Minibus, Destination, Session, TravelMatrix, TransportGroup, Allocation, FleetSchedule};
+use super::strategy::{AllocationStrategy, AllocationOutcome, split_crews};
+use super::credentials::Licensed;
+use super::equipment::DepartureKit;
+
+// --- STRATEGIES FOR SQUADS THAT CARE ABOUT SOMETHING OTHER THAN THE GREEDY DEFAULTS ---
+// All three choose their vehicles the same way (`pick_vehicles`) and differ in which vehicles they reach for first
+// and in how they seat the passengers once the vehicles are chosen.
+
+// --- USES CARS BEFORE MINIBUSES SO AS FEW PEOPLE AS POSSIBLE HAVE TO DRIVE A MINIBUS ---
+pub struct FewestMinibusDrivers;
+
+impl AllocationStrategy for FewestMinibusDrivers {
+    fn allocate(
+        &self,
+        requests: Vec<(Destination, String, Vec<Person>)>,
+        minibuses: &[Minibus],
+        session: &Session,
+        matrix: &TravelMatrix,
+        licensed: &Licensed,
+    ) -> AllocationOutcome {
+        let mut outcome = allocate_by_group(requests, minibuses, session, matrix, licensed, true, |groups, mut pool| {
+            for g in groups.iter_mut() {
+                while g.free_seats() > 0 && !pool.is_empty() {
+                    let p_idx = Allocation::find_best_passenger(&pool, g);
+                    g.passengers.push(pool.remove(p_idx));
+                }
+            }
+        });
+
+        if let Ok(groups) = &outcome.result {
+            let used = groups.iter().filter(|g| g.is_minibus()).count();
+            outcome.diagnostics.push(format!("{} minibus driver(s) needed, {} minibus(es) left at the depot.", used, minibuses.len() - used));
+        }
+        outcome
+    }
+}
+
+// --- SEATS WHOLE CREWS TOGETHER, ONLY SPLITTING A BOAT WHEN NO VEHICLE HAS ROOM FOR ALL OF IT ---
+pub struct KeepCrewsTogether;
+
+impl AllocationStrategy for KeepCrewsTogether {
+    fn allocate(
+        &self,
+        requests: Vec<(Destination, String, Vec<Person>)>,
+        minibuses: &[Minibus],
+        session: &Session,
+        matrix: &TravelMatrix,
+        licensed: &Licensed,
+    ) -> AllocationOutcome {
+        let crew_index = session.crew_index();
+
+        let mut outcome = allocate_by_group(requests, minibuses, session, matrix, licensed, false, |groups, pool| {
+
+            // Gathering this departure group into crews (anyone not in a boat is a crew of one)
+            let mut crews: Vec<(Option<usize>, Vec<Person>)> = Vec::new();
+            for p in pool {
+                let crew = crew_index.get(&p.student_id).copied();
+                match crews.iter_mut().find(|(c, _)| crew.is_some() && *c == crew) {
+                    Some((_, members)) => members.push(p),
+                    None => crews.push((crew, vec![p])),
+                }
+            }
+
+            // Crews whose driver has already been chosen go first so they can still join them, then the largest
+            crews.sort_by_key(|(crew, members)| Reverse((groups.iter().any(|g| crew_on_board(g, *crew, &crew_index) > 0), members.len())));
+
+            for (crew, mut members) in crews {
+                while !members.is_empty() {
+
+                    // Prefer the vehicle already carrying some of this crew (e.g. their driver), then the tightest fit for
+                    // everyone left, and failing that the emptiest vehicle so the crew is split as few ways as possible
+                    let target = groups.iter().enumerate()
+                        .filter(|(_, g)| g.free_seats() > 0)
+                        .max_by_key(|(_, g)| {
+                            let fits = g.free_seats() >= members.len();
+                            let room = if fits { Reverse(g.free_seats()) } else { Reverse(usize::MAX - g.free_seats()) };
+                            (crew_on_board(g, crew, &crew_index), fits, room)
+                        })
+                        .map(|(i, _)| i);
+                    let Some(g_idx) = target else { break };
+
+                    // Whoever can get in at the vehicle's starting point goes first
+                    let g = &mut groups[g_idx];
+                    members.sort_by_key(|p| !p.pickup_locations.contains(&g.pickup_location));
+                    let take = g.free_seats().min(members.len());
+                    g.passengers.extend(members.drain(..take));
+                }
+            }
+        });
+
+        if let Ok(groups) = &outcome.result {
+            for (b_idx, vehicles) in split_crews(groups, session) {
+                outcome.diagnostics.push(format!(
+                    "Boat {} ({}) had to be split across {} vehicles.",
+                    b_idx + 1, session.boats[b_idx].boat_type.label(), vehicles,
+                ));
+            }
+        }
+        outcome
+    }
+}
+
+// --- MIXES CREWS AND YEARS IN EVERY VEHICLE WHILE KEEPING THE GENDER BALANCE ---
+pub struct BalancedIntegration;
+
+impl AllocationStrategy for BalancedIntegration {
+    fn allocate(
+        &self,
+        requests: Vec<(Destination, String, Vec<Person>)>,
+        minibuses: &[Minibus],
+        session: &Session,
+        matrix: &TravelMatrix,
+        licensed: &Licensed,
+    ) -> AllocationOutcome {
+        let crew_index = session.crew_index();
+
+        let mut outcome = allocate_by_group(requests, minibuses, session, matrix, licensed, false, |groups, mut pool| {
+
+            // Alternating the most and least experienced so seniors and novices are spread out
+            pool.sort_by_key(|p| p.year_of_entry);
+            let mut order = Vec::new();
+            while !pool.is_empty() {
+                order.push(pool.remove(0));
+                if let Some(p) = pool.pop() {
+                    order.push(p);
+                }
+            }
+
+            for p in order {
+                let crew   = crew_index.get(&p.student_id).copied();
+                let target = groups.iter().enumerate()
+                    .filter(|(_, g)| g.free_seats() > 0)
+                    .min_by_key(|(_, g)| {
+                        let on_board  = std::iter::once(&g.driver).chain(&g.passengers);
+                        let same_year = on_board.clone().filter(|q| q.year_of_entry == p.year_of_entry).count();
+                        let same_sex  = on_board.clone().filter(|q| q.gender == p.gender).count();
+                        let other_sex = on_board.count() - same_sex;
+                        (
+                            crew_on_board(g, crew, &crew_index),            // Crewmates already see enough of each other
+                            same_year,
+                            same_sex.saturating_sub(other_sex),
+                            !p.pickup_locations.contains(&g.pickup_location),
+                            Reverse(g.free_seats()),                        // Spread people evenly across the vehicles
+                        )
+                    })
+                    .map(|(i, _)| i);
+
+                if let Some(g_idx) = target {
+                    groups[g_idx].passengers.push(p);
+                }
+            }
+        });
+
+        if let Ok(groups) = &outcome.result
+            && !groups.is_empty()
+        {
+            let (mut crews, mut years) = (0, 0);
+            for g in groups {
+                let on_board: Vec<&Person> = std::iter::once(&g.driver).chain(&g.passengers).collect();
+                crews += on_board.iter().filter_map(|p| crew_index.get(&p.student_id)).collect::<HashSet<_>>().len();
+                years += on_board.iter().map(|p| p.year_of_entry).collect::<HashSet<_>>().len();
+            }
+            outcome.diagnostics.push(format!(
+                "Each vehicle mixes {:.1} crews and {:.1} years of entry on average.",
+                crews as f32 / groups.len() as f32, years as f32 / groups.len() as f32,
+            ));
+        }
+        outcome
+    }
+}
+
+// --- RUNS `seat` OVER EACH DEPARTURE GROUP ONCE ITS VEHICLES HAVE BEEN CHOSEN, THEN PLANS ROUTES AND CHECKS EVERYONE GOT A SEAT ---
+fn allocate_by_group(
+    requests: Vec<(Destination, String, Vec<Person>)>,
+    minibuses: &[Minibus],
+    session: &Session,
+    matrix: &TravelMatrix,
+    licensed: &Licensed,
+    cars_first: bool,
+    mut seat: impl FnMut(&mut [TransportGroup], Vec<Person>),
+) -> AllocationOutcome {
+    let all_requests = requests.clone();
+
+    let mut schedule = FleetSchedule::new(minibuses);
+
+    let mut final_allocations = Vec::new();
+    for (dest, time, people) in requests {
+        let (mut groups, pool) = pick_vehicles((&dest, &time), people, &mut schedule, session, matrix, licensed, cars_first);
+        seat(&mut groups, pool);
+        final_allocations.extend(groups);
+    }
+
+    AllocationOutcome::new(Allocation::finish(final_allocations, &all_requests, minibuses, matrix, licensed))
+}
+
+// --- CHOOSES DRIVERS AND EMPTY VEHICLES FOR ONE DEPARTURE GROUP UNTIL THERE IS A SEAT FOR EVERYONE LEFT ---
+// Cars first only stands when it seats as many as minibuses first. Handing a car to the group's only licensed
+// minibus driver can leave a minibus with nobody to drive it, and then the car choices are undone
+fn pick_vehicles(
+    departure: (&Destination, &str),
+    pool: Vec<Person>,
+    schedule: &mut FleetSchedule,
+    session: &Session,
+    matrix: &TravelMatrix,
+    licensed: &Licensed,
+    cars_first: bool,
+) -> (Vec<TransportGroup>, Vec<Person>) {
+    if !cars_first {
+        return pick_in_order(departure, pool, schedule, session, matrix, licensed, [true, false]);
+    }
+
+    let mut cars_schedule = schedule.clone();
+    let by_car = pick_in_order(departure, pool.clone(), &mut cars_schedule, session, matrix, licensed, [false, true]);
+    if unseated(&by_car) > 0 {
+        let mut minibus_schedule = schedule.clone();
+        let by_minibus = pick_in_order(departure, pool, &mut minibus_schedule, session, matrix, licensed, [true, false]);
+        if unseated(&by_minibus) < unseated(&by_car) {
+            *schedule = minibus_schedule;
+            return by_minibus;
+        }
+    }
+    *schedule = cars_schedule;
+    by_car
+}
+
+// --- HOW MANY PEOPLE THE CHOSEN VEHICLES HAVE NO SEAT FOR ---
+fn unseated((groups, pool): &(Vec<TransportGroup>, Vec<Person>)) -> usize {
+    pool.len().saturating_sub(groups.iter().map(|g| g.free_seats()).sum())
+}
+
+// --- PICKS VEHICLES IN THE GIVEN ORDER OF PHASES (true FOR MINIBUSES, false FOR CARS) ---
+// Minibuses go largest first, if not already out at the time, with a vehicle that can tow taken first while the
+// departure has a trailer waiting. The departure's kit is loaded as the vehicles are chosen; returns the vehicles
+// and the people still to be seated
+fn pick_in_order(
+    (dest, time): (&Destination, &str),
+    mut pool: Vec<Person>,
+    schedule: &mut FleetSchedule,
+    session: &Session,
+    matrix: &TravelMatrix,
+    licensed: &Licensed,
+    phases: [bool; 2],
+) -> (Vec<TransportGroup>, Vec<Person>) {
+    let mut groups: Vec<TransportGroup> = Vec::new();
+    let mut kit = DepartureKit::for_departure(&session.equipment, dest, time);
+
+    for use_minibus in phases {
+        while pool.len() > groups.iter().map(|g| g.free_seats()).sum::<usize>() {
+            let towing       = if use_minibus { kit.towing_minibus(&pool, schedule, (dest, time), matrix, licensed) } else { None };
+            let free_minibus = towing.as_ref().map(|(mb, _)| mb.clone()).or_else(|| schedule.first_free(dest, time, matrix));
+            let d_idx = if use_minibus {
+                if free_minibus.is_none() || pool.len() < 2 { break; }    // A minibus isn't worth it for one person
+                towing.map(|(_, d_idx)| d_idx)
+                    .or_else(|| Allocation::find_willing_minibus_driver(&pool, session, licensed))
+                    .or_else(|| pool.iter().position(|p| licensed.minibus(p)))
+            } else {
+                // Largest car first, and among equal cars keep licensed minibus drivers free for the minibuses
+                kit.towing_car(&pool).or_else(|| Allocation::find_willing_car_driver(&pool, session)).or_else(|| {
+                    pool.iter().enumerate()
+                        .filter(|(_, p)| p.car.is_some())
+                        .max_by_key(|(_, p)| (p.car.as_ref().unwrap().seats, !licensed.minibus(p)))
+                        .map(|(idx, _)| idx)
+                })
+            };
+            let Some(d_idx) = d_idx else { break };
+
+            let driver = pool.remove(d_idx);
+            groups.push(match free_minibus {
+                Some(mb) if use_minibus => {
+                    schedule.book(&mb, &driver.name, dest, time, matrix);
+                    TransportGroup::minibus(driver, &mb, dest, time)
+                }
+                _ => TransportGroup::car(driver, dest, time),
+            });
+            kit.reserve(&mut groups);
+        }
+    }
+    (groups, pool)
+}
+
+// --- HOW MANY OF A CREW ARE ALREADY IN A VEHICLE (DRIVER INCLUDED) ---
+fn crew_on_board(group: &TransportGroup, crew: Option<usize>, crew_index: &HashMap<String, usize>) -> usize {
+    let Some(crew) = crew else { return 0 };
+    std::iter::once(&group.driver).chain(&group.passengers)
+        .filter(|p| crew_index.get(&p.student_id) == Some(&crew))
+        .count()
+}
@@ -0,0 +1,234 @@
+use std::collections::{HashMap, HashSet};
+use super::{Person, Minibus, Gender, Destination, Session, TravelMatrix, TransportGroup, AllocationReport};
+use super::allocations::{Allocation, Greedy};
+use super::solver::OptimalSolver;
+use super::squad_strategies::{FewestMinibusDrivers, KeepCrewsTogether, BalancedIntegration};
+use super::returns::plan_journeys_home;
+use super::edit::apply_locks;
+use super::constraints::{prepare, apply_constraints, violations};
+use super::equipment::{load_equipment, equipment_problems};
+use super::credentials::{refusals, Licensed};
+use super::history::{DriverHistory, rotate_drivers};
+
+// --- COMMON INTERFACE FOR THE DIFFERENT WAYS OF PACKING PEOPLE INTO VEHICLES ---
+// Driver preferences come in through the session ("Wants to Drive" overrides) along with the crews themselves, and who
+// is licensed to drive on the day is worked out once beforehand
+pub trait AllocationStrategy {
+    fn allocate(
+        &self,
+        requests: Vec<(Destination, String, Vec<Person>)>,  // List of (Destination, Departure Time, People Requesting Transport)
+        minibuses: &[Minibus],
+        session: &Session,
+        matrix: &TravelMatrix,
+        licensed: &Licensed,
+    ) -> AllocationOutcome;
+}
+
+// --- WHAT A STRATEGY HANDS BACK: THE GROUPS (OR WHO COULDN'T BE SEATED AND WHY) PLUS NOTES ON HOW IT WENT ---
+pub struct AllocationOutcome {
+    pub result: Result<Vec<TransportGroup>, AllocationReport>,
+    pub diagnostics: Vec<String>,           // One line each, shown after publishing and printed by the command line
+}
+
+impl AllocationOutcome {
+    pub fn new(result: Result<Vec<TransportGroup>, AllocationReport>) -> Self {
+        AllocationOutcome { result, diagnostics: Vec::new() }
+    }
+}
+
+// --- THE STRATEGIES THAT CAN BE PICKED FROM THE UI AND COMMAND LINE ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StrategyKind {
+    #[default]
+    Greedy,
+    FewestVehicles,
+    FewestMinibusDrivers,
+    KeepCrewsTogether,
+    BalancedIntegration,
+}
+
+impl StrategyKind {
+    pub const ALL: [StrategyKind; 5] = [
+        StrategyKind::Greedy,
+        StrategyKind::FewestVehicles,
+        StrategyKind::FewestMinibusDrivers,
+        StrategyKind::KeepCrewsTogether,
+        StrategyKind::BalancedIntegration,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StrategyKind::Greedy => "Greedy",
+            StrategyKind::FewestVehicles => "Fewest vehicles",
+            StrategyKind::FewestMinibusDrivers => "Fewest minibus drivers",
+            StrategyKind::KeepCrewsTogether => "Keep crews together",
+            StrategyKind::BalancedIntegration => "Balanced integration",
+        }
+    }
+
+    // Name used by the command line "--strategy" option (e.g. "keep-crews-together")
+    pub fn name(&self) -> String {
+        self.label().to_lowercase().replace(' ', "-")
+    }
+
+    // Accepts either the command line name or the label shown in the app (case insensitive)
+    pub fn from_name(name: &str) -> Option<StrategyKind> {
+        Self::ALL.into_iter().find(|k| k.name().eq_ignore_ascii_case(name) || k.label().eq_ignore_ascii_case(name))
+    }
+
+    pub fn strategy(&self) -> Box<dyn AllocationStrategy> {
+        match self {
+            StrategyKind::Greedy => Box::new(Greedy),
+            StrategyKind::FewestVehicles => Box::new(OptimalSolver),
+            StrategyKind::FewestMinibusDrivers => Box::new(FewestMinibusDrivers),
+            StrategyKind::KeepCrewsTogether => Box::new(KeepCrewsTogether),
+            StrategyKind::BalancedIntegration => Box::new(BalancedIntegration),
+        }
+    }
+
+    // --- RUNS THIS STRATEGY AND PLANS THE JOURNEYS HOME, THEN SUMMARISES THE RESULT AND ---
+    // --- (FOR ANYTHING OTHER THAN GREEDY) HOW FAR THE GREEDY PASS WOULD HAVE BEEN FROM IT ---
+    // With a driving history (the fair rota) each vehicle is then handed to whoever on board has driven least recently
+    pub fn run(
+        &self,
+        requests: Vec<(Destination, String, Vec<Person>)>,
+        minibuses: &[Minibus],
+        session: &Session,
+        matrix: &TravelMatrix,
+        rota: Option<&DriverHistory>,
+    ) -> AllocationOutcome {
+        // Drivers whose licence, assessment or insurance doesn't cover the outing are noted before they're taken off the road,
+        // then private travellers and who may drive are settled up front, so every strategy honours them
+        let on      = session.outing_date();
+        let refused = refusals(&requests, on);
+        let (requests, constrained) = prepare(requests, session, on);
+        let session  = &constrained;
+        let licensed = Licensed::on(requests.iter().flat_map(|(_, _, people)| people), on);
+
+        let mut outcome = self.strategy().allocate(requests.clone(), minibuses, session, matrix, &licensed);
+
+        // A partial plan gets the same summary, journeys home and checks, so it can still be published
+        let (groups, unallocated) = match &mut outcome.result {
+            Ok(groups) => (groups, 0),
+            Err(report) => {
+                let unallocated = report.unallocated_count();
+                (&mut report.partial, unallocated)
+            }
+        };
+
+        // The fair rota swaps drivers first, then the remaining constraints and choices locked by hand in the Transport view
+        // are put back before anything is scored, and the session's equipment is loaded onto whatever can take it
+        if rota.is_some() || !session.constraints.is_empty() || !session.locks.is_empty() || !session.equipment.is_empty() {
+            if let Some(history) = rota {
+                outcome.diagnostics.extend(rotate_drivers(groups, history, session));
+            }
+            apply_constraints(groups, &session.constraints, on);
+            outcome.diagnostics.extend(apply_locks(groups, &session.locks, on));
+            load_equipment(groups, session, minibuses, matrix);
+            Allocation::plan_routes(groups, matrix);
+        }
+
+        let score   = AllocationScore::of(groups, unallocated);
+        let summary = format!(
+            "{}: {} vehicle(s) ({} minibus), {} pickup mismatch(es), gender imbalance {}, {} crew(s) split.",
+            self.label(), score.vehicles, groups.iter().filter(|g| g.is_minibus()).count(),
+            score.pickup_mismatches, score.gender_imbalance, split_crews(groups, session).len(),
+        );
+        outcome.diagnostics.insert(0, summary);
+        outcome.diagnostics.extend(refused);
+
+        outcome.diagnostics.extend(plan_journeys_home(groups, session, matrix));
+        outcome.diagnostics.extend(violations(groups, &session.constraints));
+        outcome.diagnostics.extend(equipment_problems(groups, &session.equipment, matrix));
+
+        if *self != StrategyKind::Greedy {
+            let greedy = match Greedy.allocate(requests, minibuses, session, matrix, &licensed).result {
+                Ok(greedy_groups) => AllocationScore::of(&greedy_groups, 0),
+                Err(report) => AllocationScore::of(&report.partial, report.unallocated_count()),
+            };
+            outcome.diagnostics.push(score.gap_from_greedy(&greedy));
+        }
+        outcome
+    }
+}
+
+// --- BOATS WHOSE CREW ENDS UP IN MORE THAN ONE VEHICLE, AS (BOAT INDEX, NUMBER OF VEHICLES) ---
+pub fn split_crews(groups: &[TransportGroup], session: &Session) -> Vec<(usize, usize)> {
+    let crews = session.crew_index();
+    let mut vehicles_per_crew: HashMap<usize, HashSet<usize>> = HashMap::new();
+    for (g_idx, g) in groups.iter().enumerate() {
+        for p in std::iter::once(&g.driver).chain(&g.passengers) {
+            if let Some(&crew) = crews.get(&p.student_id) {
+                vehicles_per_crew.entry(crew).or_default().insert(g_idx);
+            }
+        }
+    }
+    let mut split: Vec<(usize, usize)> = vehicles_per_crew.into_iter()
+        .filter(|(_, vehicles)| vehicles.len() > 1)
+        .map(|(crew, vehicles)| (crew, vehicles.len()))
+        .collect();
+    split.sort();
+    split
+}
+
+// --- HOW GOOD AN ALLOCATION IS, COMPARED IN ORDER (FEWER OF EACH IS BETTER) ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct AllocationScore {
+    pub unallocated: usize,                 // People left without a seat
+    pub vehicles: usize,                    // Vehicles used
+    pub pickup_mismatches: usize,           // Passengers who can't get in at their vehicle's starting pickup point
+    pub gender_imbalance: usize,            // Sum over vehicles of |men - women| (driver included)
+}
+
+impl std::ops::Add for AllocationScore {
+    type Output = AllocationScore;
+
+    fn add(self, other: AllocationScore) -> AllocationScore {
+        AllocationScore {
+            unallocated: self.unallocated + other.unallocated,
+            vehicles: self.vehicles + other.vehicles,
+            pickup_mismatches: self.pickup_mismatches + other.pickup_mismatches,
+            gender_imbalance: self.gender_imbalance + other.gender_imbalance,
+        }
+    }
+}
+
+impl AllocationScore {
+    pub fn of(groups: &[TransportGroup], unallocated: usize) -> Self {
+        let mut score = AllocationScore { unallocated, vehicles: groups.len(), ..Default::default() };
+        for g in groups {
+            score.pickup_mismatches += Self::vehicle_mismatches(&g.pickup_location, &g.passengers);
+            score.gender_imbalance  += Self::vehicle_imbalance(&g.driver, &g.passengers);
+        }
+        score
+    }
+
+    pub fn vehicle_mismatches(start: &str, passengers: &[Person]) -> usize {
+        passengers.iter().filter(|p| !p.pickup_locations.iter().any(|l| l == start)).count()
+    }
+
+    pub fn vehicle_imbalance(driver: &Person, passengers: &[Person]) -> usize {
+        let males = std::iter::once(driver).chain(passengers).filter(|p| p.gender == Gender::Male).count();
+        let total = passengers.len() + 1;
+        males.abs_diff(total - males)
+    }
+
+    // --- ONE LINE SUMMARY OF HOW MUCH WORSE THE GREEDY RESULT IS THAN THIS ONE ---
+    pub fn gap_from_greedy(&self, greedy: &AllocationScore) -> String {
+        if greedy.unallocated > self.unallocated {
+            return format!("Greedy would have left {} more people without transport.", greedy.unallocated - self.unallocated);
+        }
+        if greedy.unallocated < self.unallocated {
+            return format!("Greedy would have seated {} more people.", self.unallocated - greedy.unallocated);
+        }
+        if greedy == self {
+            return "Greedy scores the same on vehicles, pickup points and gender balance.".to_string();
+        }
+        format!(
+            "Greedy would use {} vehicle(s) ({:+}), {} pickup mismatch(es) ({:+}), gender imbalance {} ({:+}).",
+            greedy.vehicles, greedy.vehicles as i64 - self.vehicles as i64,
+            greedy.pickup_mismatches, greedy.pickup_mismatches as i64 - self.pickup_mismatches as i64,
+            greedy.gender_imbalance, greedy.gender_imbalance as i64 - self.gender_imbalance as i64,
+        )
+    }
+}
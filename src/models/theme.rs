@@ -0,0 +1,69 @@
+use serde::{Serialize, Deserialize};
+
+// --- PAPER SIZES THE SHEETS CAN BE PRINTED ON ---
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PaperSize {
+    #[default]
+    A4,
+    A3,
+    Letter,
+}
+
+impl PaperSize {
+    // (Width, height) in mm, held portrait
+    pub fn portrait_mm(&self) -> (f32, f32) {
+        match self {
+            PaperSize::A4 => (210.0, 297.0),
+            PaperSize::A3 => (297.0, 420.0),
+            PaperSize::Letter => (215.9, 279.4),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Orientation {
+    Portrait,
+    #[default]
+    Landscape,
+}
+
+// --- HOW THE CLUB'S PDFS LOOK (LOADED FROM pdf_theme.json, EVERY FIELD OPTIONAL) ---
+// Without the file the sheets come out as they always have: Helvetica, black outlines and 45mm columns on A4 landscape
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfTheme {
+    pub club_name: String,                  // Printed at the top of every page and in the footer (blank leaves it off)
+    pub logo: String,                       // Path to a JPEG crest drawn in the top left corner (blank for none)
+    pub primary: [f32; 3],                  // Crest colour (RGB 0.0 - 1.0) for the club name and the rule under the heading
+    pub outline: [f32; 3],                  // Crest colour for the box outlines on the transport sheet
+    pub font: String,                       // Path to a TTF embedded for ordinary text (blank uses Helvetica)
+    pub bold_font: String,                  // Path to a TTF for bold text (blank uses `font`, or Helvetica Bold)
+    pub column_width: f32,                  // Width of a vehicle or crew column on the transport sheet, in mm
+    pub paper: PaperSize,
+    pub orientation: Orientation,           // Of the transport sheet (slips, itineraries and claims are always portrait)
+}
+
+impl PdfTheme {
+    pub const MIN_COLUMN_WIDTH: f32 = 30.0;
+
+    // Narrower columns can't fit a name, so anything smaller is widened
+    pub fn column_width(&self) -> f32 {
+        self.column_width.max(Self::MIN_COLUMN_WIDTH)
+    }
+}
+
+impl Default for PdfTheme {
+    fn default() -> Self {
+        PdfTheme {
+            club_name: String::new(),
+            logo: String::new(),
+            primary: [0.0, 0.0, 0.0],
+            outline: [0.0, 0.0, 0.0],
+            font: String::new(),
+            bold_font: String::new(),
+            column_width: 45.0,
+            paper: PaperSize::A4,
+            orientation: Orientation::Landscape,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use serde::{Serialize, Deserialize};
+use super::Destination;
+
+// --- A RACING / TRAINING VENUE THAT BOATS CAN BE SENT TO (LOADED FROM venues.json) ---
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Venue {
+    pub id: Destination,                    // Stable key stored on boats and sessions (never changes once created)
+    pub name: String,                       // Label shown in the app and on the transport sheet
+    pub colour: [f32; 3],                   // RGB (0.0 - 1.0) used for this venue's header boxes on the transport sheet
+    #[serde(default)]
+    pub address: String,
+    #[serde(default)]
+    pub latitude: f64,
+    #[serde(default)]
+    pub longitude: f64,
+    #[serde(default)]
+    pub travel_minutes: u32,                // Typical drive time from the boathouse
+    #[serde(default = "Venue::default_session_minutes")]
+    pub session_minutes: u32,               // Typical time crews spend at the venue (vehicles wait for them)
+}
+
+impl Venue {
+    pub fn default_session_minutes() -> u32 {
+        120
+    }
+
+    // --- THE TWO VENUES THE APP ORIGINALLY SHIPPED WITH (USED WHEN THERE IS NO venues.json YET) ---
+    pub fn defaults() -> Vec<Venue> {
+        vec![
+            Venue {
+                id: Destination("StrathclydePark".to_string()),
+                name: "Strathclyde Park".to_string(),
+                colour: [1.0, 0.9, 0.8],
+                address: "366 Hamilton Rd, Motherwell ML1 3ED".to_string(),
+                latitude: 55.7966,
+                longitude: -4.0417,
+                travel_minutes: 40,
+                session_minutes: Venue::default_session_minutes(),
+            },
+            Venue {
+                id: Destination("Auchenstarry".to_string()),
+                name: "Auchenstarry".to_string(),
+                colour: [0.9, 0.8, 0.9],
+                address: "Auchenstarry Marina, Kilsyth G65 9SG".to_string(),
+                latitude: 55.9717,
+                longitude: -4.0561,
+                travel_minutes: 45,
+                session_minutes: Venue::default_session_minutes(),
+            },
+        ]
+    }
+
+    // --- BUILDS A STABLE ID FROM A NEW VENUE'S NAME (e.g. "Eton Dorney" -> "EtonDorney") ---
+    pub fn id_from_name(name: &str) -> Destination {
+        Destination(name.chars().filter(|c| c.is_alphanumeric()).collect())
+    }
+}
+
+// --- LOOKUPS OVER A LIST OF VENUES (FALLING BACK GRACEFULLY FOR VENUES THAT HAVE SINCE BEEN DELETED) ---
+pub fn find_venue<'a>(venues: &'a [Venue], dest: &Destination) -> Option<&'a Venue> {
+    venues.iter().find(|v| &v.id == dest)
+}
+
+pub fn venue_label<'a>(venues: &'a [Venue], dest: &'a Destination) -> &'a str {
+    find_venue(venues, dest).map(|v| v.name.as_str()).unwrap_or(&dest.0)
+}
+
+pub fn venue_colour(venues: &[Venue], dest: &Destination) -> (f32, f32, f32) {
+    find_venue(venues, dest).map(|v| (v.colour[0], v.colour[1], v.colour[2])).unwrap_or((1.0, 1.0, 1.0))
+}
@@ -0,0 +1,167 @@
+use printpdf::*;
+use chrono::NaiveDate;
+use crate::models::{TransportGroup, Venue, Leg, PickupPoint};
+use crate::models::edit::TransportPlan;
+use crate::models::itinerary::Itinerary;
+use crate::models::theme::{PdfTheme, Orientation};
+use crate::models::venue::{venue_label, find_venue};
+use super::layout::{Pages, PageSetup, black, wrap};
+
+// --- ONE SLIP PER VEHICLE (EACH WAY) FOR ITS DRIVER, FROM THE SAME PLAN AS THE TRANSPORT SHEET ---
+// Who to collect in the order they're picked up (or dropped off), where and when, the venue's address and everyone's phone number
+pub fn generate_driver_slips(plan: &TransportPlan, venues: &[Venue], pickup_points: &[PickupPoint], theme: &PdfTheme, on: NaiveDate, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let date      = on.format("%A %-d %B %Y").to_string();
+    let setup     = PageSetup::new(theme.paper, Orientation::Portrait);
+    let mut pages = Pages::new("Driver Slips", setup, theme, &date, &slip_title(plan.groups.first(), venues))?;
+
+    if plan.groups.is_empty() {
+        let top = pages.setup.content_top() - 8.0;
+        pages.layer().use_text("No vehicles in this plan.", 11.0, Mm(10.0), Mm(top), &pages.font);
+    }
+    for (idx, group) in plan.groups.iter().enumerate() {
+        if idx > 0 {
+            pages.new_page(&slip_title(Some(group), venues));
+        }
+        draw_slip(&mut pages, group, venues, pickup_points);
+    }
+
+    pages.save(filename)
+}
+
+fn slip_title(group: Option<&TransportGroup>, venues: &[Venue]) -> String {
+    match group {
+        None => "Driver slips".to_string(),
+        Some(g) if g.leg == Leg::Return => format!("{} - home from {}", g.vehicle_label, venue_label(venues, &g.destination)),
+        Some(g) => format!("{} - to {}", g.vehicle_label, venue_label(venues, &g.destination)),
+    }
+}
+
+fn draw_slip(pages: &mut Pages, group: &TransportGroup, venues: &[Venue], pickup_points: &[PickupPoint]) {
+    let (font, font_bold) = (pages.font.clone(), pages.font_bold.clone());
+    let setup = pages.setup;
+
+    // --- LAYOUT SETTINGS ---
+    let bottom     = setup.content_bottom() + 4.0;
+    let row_height = 6.0;
+    let columns    = [10.0, 25.0, 70.0, 140.0];    // Time, place, name, phone
+
+    let mut layer    = pages.layer().clone();
+    let mut y_cursor = setup.content_top() - 4.0;
+    layer.set_fill_color(black());
+
+    // --- THE JOURNEY AT A GLANCE ---
+    let venue   = find_venue(venues, &group.destination);
+    let name    = venue_label(venues, &group.destination);
+    let address = venue.map(|v| v.address.as_str()).filter(|a| !a.is_empty()).unwrap_or("address not recorded");
+    let phone   = |number: &str| if number.is_empty() { "no number".to_string() } else { number.to_string() };
+
+    let mut summary = vec![
+        (format!("Driver: {} ({})", group.driver.name, phone(&group.driver.phone)), 12.0, true),
+        (format!("{} - {} of {} seats taken", group.vehicle_label, group.passengers.len(), group.capacity), 10.0, false),
+    ];
+    match group.leg {
+        Leg::Outbound => {
+            let (start, time) = group.stops.first()
+                .map_or((group.pickup_location.as_str(), group.departure_time.as_str()), |s| (s.location.as_str(), s.time.as_str()));
+            summary.push((format!("Leave {} at {}", start, time), 11.0, true));
+            if !group.arrival_time.is_empty() {
+                summary.push((format!("Arrive at {} about {}", name, group.arrival_time), 10.0, false));
+            }
+        }
+        Leg::Return => {
+            summary.push((format!("Leave {} at {}", name, group.departure_time), 11.0, true));
+            if !group.arrival_time.is_empty() {
+                summary.push((format!("Finish at {} about {}", group.pickup_location, group.arrival_time), 10.0, false));
+            }
+        }
+    }
+    summary.push((format!("Venue: {}, {}", name, address), 10.0, false));
+    if !group.equipment.is_empty() {
+        summary.push((format!("Carrying {}", group.load_summary()), 10.0, true));
+    }
+
+    for (text, size, bold) in summary {
+        layer.use_text(text, size, Mm(10.0), Mm(y_cursor), if bold { &font_bold } else { &font });
+        y_cursor -= row_height;
+    }
+    y_cursor -= row_height / 2.0;
+
+    // --- EVERY STOP IN ORDER, WITH WHO GETS IN (OR OUT) THERE ---
+    let heading = if group.leg == Leg::Return { "Drop-offs in order" } else { "Pickups in order" };
+    for (text, x) in ["Time", heading, "Name", "Phone"].iter().zip(columns) {
+        layer.use_text(*text, 9.0, Mm(x), Mm(y_cursor), &font_bold);
+    }
+
+    for stop in &group.stops {
+        let notes = pickup_points.iter().find(|p| p.name == stop.location).map(|p| p.notes.as_str()).unwrap_or("");
+        for (n_idx, person_name) in stop.people.iter().enumerate() {
+            y_cursor -= row_height;
+            if y_cursor < bottom {
+                layer    = pages.new_page(&format!("{} (continued)", slip_title(Some(group), venues))).clone();
+                y_cursor = setup.content_top() - 4.0;
+            }
+
+            let (label, number) = if *person_name == group.driver.name {
+                (format!("{} (driver)", person_name), group.driver.phone.as_str())
+            } else {
+                let number = group.passengers.iter().find(|p| p.name == *person_name).map_or("", |p| p.phone.as_str());
+                (person_name.clone(), number)
+            };
+            layer.use_text(label, 10.0, Mm(columns[2]), Mm(y_cursor), &font);
+            layer.use_text(phone(number), 10.0, Mm(columns[3]), Mm(y_cursor), &font);
+
+            // The time and place go on the first person at each stop, with where exactly to wait underneath
+            if n_idx == 0 {
+                layer.use_text(stop.time.clone(), 10.0, Mm(columns[0]), Mm(y_cursor), &font_bold);
+                layer.use_text(stop.location.clone(), 10.0, Mm(columns[1]), Mm(y_cursor), &font_bold);
+                for line in wrap(notes, 7.0, setup.width - setup.margin - columns[1]) {
+                    y_cursor -= 3.5;
+                    layer.use_text(line, 7.0, Mm(columns[1]), Mm(y_cursor), &font);
+                }
+            }
+        }
+    }
+}
+
+// --- EVERYONE'S OWN ITINERARY, ONE BLOCK EACH IN NAME ORDER, OVER AS MANY PAGES AS NEEDED ---
+pub fn generate_itinerary_pdf(itineraries: &[Itinerary], theme: &PdfTheme, on: NaiveDate, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let date      = on.format("%A %-d %B %Y").to_string();
+    let setup     = PageSetup::new(theme.paper, Orientation::Portrait);
+    let mut pages = Pages::new("Itineraries", setup, theme, &date, "Itineraries")?;
+    let (font, font_bold) = (pages.font.clone(), pages.font_bold.clone());
+
+    // --- LAYOUT SETTINGS ---
+    let setup       = pages.setup;
+    let top         = setup.content_top() - 4.0;
+    let bottom      = setup.content_bottom() + 4.0;
+    let width       = setup.width - setup.margin * 2.0 - 5.0;
+    let line_height = 4.5;
+    let gap         = 3.0;
+
+    let mut layer    = pages.layer().clone();
+    let mut y_cursor = top;
+
+    if itineraries.is_empty() {
+        layer.use_text("Nobody is travelling in this plan.", 11.0, Mm(10.0), Mm(y_cursor), &font);
+    }
+
+    for itinerary in itineraries {
+        let lines: Vec<String> = itinerary.steps.iter().flat_map(|step| wrap(step, 9.0, width)).collect();
+
+        // A person's block is never split over two pages
+        let height = line_height * (lines.len() + 1) as f32;
+        if y_cursor - height < bottom {
+            layer    = pages.new_page("Itineraries (continued)").clone();
+            y_cursor = top;
+        }
+
+        layer.use_text(itinerary.name.clone(), 10.0, Mm(10.0), Mm(y_cursor), &font_bold);
+        for line in lines {
+            y_cursor -= line_height;
+            layer.use_text(line, 9.0, Mm(15.0), Mm(y_cursor), &font);
+        }
+        y_cursor -= line_height + gap;
+    }
+
+    pages.save(filename)
+}
@@ -0,0 +1,448 @@
+use printpdf::*;
+use printpdf::path::{PaintMode, WindingOrder};
+use std::fs::File;
+use std::io::BufWriter;
+use crate::models::theme::{PdfTheme, PaperSize, Orientation};
+
+// --- PAPER SIZE AND THE SPACE KEPT CLEAR AROUND THE CONTENT (ALL IN MM) ---
+#[derive(Debug, Clone, Copy)]
+pub struct PageSetup {
+    pub width: f32,
+    pub height: f32,
+    pub margin: f32,                        // Left and right edges
+    pub header: f32,                        // Band at the top for the club, section title and date
+    pub footer: f32,                        // Band at the bottom for "Page N of M"
+}
+
+impl PageSetup {
+    pub fn new(paper: PaperSize, orientation: Orientation) -> Self {
+        let (short, long) = paper.portrait_mm();
+        let (width, height) = match orientation {
+            Orientation::Portrait => (short, long),
+            Orientation::Landscape => (long, short),
+        };
+        PageSetup { width, height, margin: 10.0, header: 22.0, footer: 14.0 }
+    }
+
+    pub fn content_top(&self) -> f32 {
+        self.height - self.header
+    }
+
+    pub fn content_bottom(&self) -> f32 {
+        self.footer
+    }
+}
+
+// --- A JPEG CREST, EMBEDDED AS IT IS (PDF READERS DECODE JPEG THEMSELVES) ---
+#[derive(Debug, Clone)]
+struct Logo {
+    width: usize,                           // Pixels
+    height: usize,
+    components: u8,                         // 1 = greyscale, 3 = colour, 4 = CMYK
+    data: Vec<u8>,
+}
+
+impl Logo {
+    const HEIGHT_MM: f32 = 14.0;
+
+    // Reads the size from the JPEG's start-of-frame marker, stepping over every segment before it
+    fn read(path: &str) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("Could not read the logo '{}': {}", path, e))?;
+        let not_jpeg = || format!("The logo '{}' isn't a JPEG image.", path);
+        if !data.starts_with(&[0xFF, 0xD8]) { return Err(not_jpeg()); }
+
+        let mut at = 2;
+        while at + 9 < data.len() {
+            if data[at] != 0xFF { return Err(not_jpeg()); }
+            let marker = data[at + 1];
+            let length = u16::from_be_bytes([data[at + 2], data[at + 3]]) as usize;
+            if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                return Ok(Logo {
+                    height: u16::from_be_bytes([data[at + 5], data[at + 6]]) as usize,
+                    width: u16::from_be_bytes([data[at + 7], data[at + 8]]) as usize,
+                    components: data[at + 9],
+                    data,
+                });
+            }
+            at += 2 + length;
+        }
+        Err(not_jpeg())
+    }
+
+    fn width_mm(&self) -> f32 {
+        Self::HEIGHT_MM * self.width as f32 / self.height.max(1) as f32
+    }
+
+    // --- DRAWS IT HEIGHT_MM TALL WITH ITS BOTTOM LEFT CORNER AT (x, y) ---
+    fn draw(&self, layer: &PdfLayerReference, x: f32, y: f32) {
+        let color_space = match self.components {
+            1 => ColorSpace::Greyscale,
+            4 => ColorSpace::Cmyk,
+            _ => ColorSpace::Rgb,
+        };
+        let image = ImageXObject {
+            width: Px(self.width),
+            height: Px(self.height),
+            color_space,
+            bits_per_component: ColorBits::Bit8,
+            interpolate: true,
+            image_data: self.data.clone(),
+            image_filter: Some(ImageFilter::DCT),
+            smask: None,
+            clipping_bbox: None,
+        };
+        let dpi = self.height as f32 * 25.4 / Self::HEIGHT_MM;      // Scales it to the height wanted
+        Image::from(image).add_to_layer(layer.clone(), ImageTransform { translate_x: Some(Mm(x)), translate_y: Some(Mm(y)), dpi: Some(dpi), ..Default::default() });
+    }
+}
+
+// --- THE PAGES OF ONE DOCUMENT ---
+// Every page gets the club's crest and name, its section title and the date at the top, and "Page N of M" at the bottom
+// once the count is known. Text uses the theme's fonts, so names in any script print if the TTF has them.
+pub struct Pages {
+    doc: PdfDocumentReference,
+    pub setup: PageSetup,
+    pub font: IndirectFontRef,
+    pub font_bold: IndirectFontRef,
+    title: String,                          // Document name, in the footer of every page
+    date: String,
+    club_name: String,
+    primary: (f32, f32, f32),
+    logo: Option<Logo>,
+    layers: Vec<PdfLayerReference>,
+}
+
+impl Pages {
+    pub fn new(title: &str, setup: PageSetup, theme: &PdfTheme, date: &str, section: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let (doc, page1, layer1) = PdfDocument::new(title, Mm(setup.width), Mm(setup.height), "Layer 1");
+
+        // Embedded TTFs when the theme names them, otherwise the built in Helvetica
+        let external = |path: &str| -> Result<IndirectFontRef, String> {
+            let file = File::open(path).map_err(|e| format!("Could not open the font '{}': {}", path, e))?;
+            doc.add_external_font(file).map_err(|e| format!("Could not load the font '{}': {}", path, e))
+        };
+        let font = match theme.font.trim() {
+            "" => doc.add_builtin_font(BuiltinFont::Helvetica)?,
+            path => external(path)?,
+        };
+        let font_bold = match (theme.bold_font.trim(), theme.font.trim()) {
+            ("", "") => doc.add_builtin_font(BuiltinFont::HelveticaBold)?,
+            ("", _) => font.clone(),
+            (path, _) => external(path)?,
+        };
+        let logo = match theme.logo.trim() {
+            "" => None,
+            path => Some(Logo::read(path)?),
+        };
+
+        let layer = doc.get_page(page1).get_layer(layer1);
+        let [r, g, b] = theme.primary;
+        let pages = Pages {
+            doc, setup, font, font_bold, logo,
+            title: title.to_string(),
+            date: date.to_string(),
+            club_name: theme.club_name.trim().to_string(),
+            primary: (r, g, b),
+            layers: vec![layer],
+        };
+        pages.draw_header(section);
+        Ok(pages)
+    }
+
+    // --- THE PAGE BEING DRAWN ON ---
+    pub fn layer(&self) -> &PdfLayerReference {
+        self.layers.last().unwrap()
+    }
+
+    pub fn new_page(&mut self, section: &str) -> &PdfLayerReference {
+        let (page, layer) = self.doc.add_page(Mm(self.setup.width), Mm(self.setup.height), "Layer 1");
+        self.layers.push(self.doc.get_page(page).get_layer(layer));
+        self.draw_header(section);
+        self.layer()
+    }
+
+    // --- CREST, CLUB NAME OVER THE SECTION TITLE (OR JUST THE TITLE) AND THE DATE ON THE RIGHT, WITH A RULE UNDERNEATH ---
+    fn draw_header(&self, section: &str) {
+        let layer = self.layer();
+        let top   = self.setup.height - 12.0;
+        let mut x = self.setup.margin;
+
+        if let Some(logo) = &self.logo {
+            logo.draw(layer, x, self.setup.height - 6.0 - Logo::HEIGHT_MM);
+            x += logo.width_mm() + 3.0;
+        }
+        if self.club_name.is_empty() {
+            layer.set_fill_color(black());
+            layer.use_text(section, 14.0, Mm(x), Mm(top), &self.font_bold);
+        } else {
+            layer.set_fill_color(rgb(self.primary));
+            layer.use_text(self.club_name.clone(), 14.0, Mm(x), Mm(top + 1.0), &self.font_bold);
+            layer.set_fill_color(black());
+            layer.use_text(section, 10.0, Mm(x), Mm(top - 5.0), &self.font);
+        }
+        let right = self.setup.width - self.setup.margin - text_width(&self.date, 10.0);
+        layer.use_text(self.date.clone(), 10.0, Mm(right), Mm(top), &self.font);
+
+        // The crest-coloured rule only goes on a branded sheet, so the plain one looks as it always has
+        if self.club_name.is_empty() && self.logo.is_none() { return; }
+        let rule = self.setup.content_top() + 2.5;
+        layer.set_outline_color(rgb(self.primary));
+        layer.set_outline_thickness(0.75);
+        layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(self.setup.margin), Mm(rule)), false),
+                (Point::new(Mm(self.setup.width - self.setup.margin), Mm(rule)), false),
+            ],
+            is_closed: false,
+        });
+        layer.set_outline_thickness(0.5);
+    }
+
+    // --- NUMBERS THE PAGES AND WRITES THE FILE ---
+    pub fn save(self, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let count  = self.layers.len();
+        let footer = if self.club_name.is_empty() { self.title.clone() } else { format!("{} - {}", self.club_name, self.title) };
+        for (idx, layer) in self.layers.iter().enumerate() {
+            let page_no = format!("Page {} of {}", idx + 1, count);
+            layer.set_fill_color(black());
+            layer.use_text(footer.clone(), 8.0, Mm(self.setup.margin), Mm(6.0), &self.font);
+            layer.use_text(page_no.clone(), 8.0, Mm(self.setup.width - self.setup.margin - text_width(&page_no, 8.0)), Mm(6.0), &self.font);
+        }
+        self.doc.save(&mut BufWriter::new(File::create(filename)?)).map_err(|e| e.into())
+    }
+}
+
+// --- FILL AND OUTLINE COLOURS FOR ONE COLUMN ---
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnStyle {
+    pub header: (f32, f32, f32),            // RGB
+    pub cell: (f32, f32, f32),
+    pub outline: (f32, f32, f32),
+    pub thickness: f32,                     // Outline thickness in points
+}
+
+// --- ONE LINE OF TEXT IN A COLUMN'S HEADER BOX ---
+#[derive(Debug, Clone)]
+pub struct HeaderLine {
+    pub text: String,
+    pub size: f32,
+    pub bold: bool,
+    pub y: f32,                             // Up from the bottom of the header box
+}
+
+impl HeaderLine {
+    pub fn new(text: impl Into<String>, size: f32, bold: bool, y: f32) -> Self {
+        HeaderLine { text: text.into(), size, bold, y }
+    }
+}
+
+// --- A BOXED ROW UNDER THE HEADER: A PLAIN NAME, OR A NAME WITH SMALL PRINT UNDERNEATH ---
+#[derive(Debug, Clone)]
+pub enum Row {
+    Name(String),
+    Detailed(String, String),
+}
+
+// --- A COLUMN ON THE SHEET: HEADER BOX, ONE BOXED ROW PER PERSON, THEN UNBOXED NOTES (STOPS, LOADS, ARRIVAL) ---
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub header: Vec<HeaderLine>,
+    pub rows: Vec<Row>,
+    pub notes: Vec<(String, bool)>,         // (Text, bold)
+    pub style: ColumnStyle,
+}
+
+impl Column {
+    pub const HEADER_HEIGHT: f32 = 20.0;
+    pub const ROW_HEIGHT: f32    = 7.0;
+    pub const NOTE_HEIGHT: f32   = 4.0;
+    const NOTES_GAP: f32         = 2.0;     // Between the last row and the first note
+
+    pub fn height(&self) -> f32 {
+        let notes = if self.notes.is_empty() { 0.0 } else { Self::NOTES_GAP + self.notes.len() as f32 * Self::NOTE_HEIGHT };
+        Self::HEADER_HEIGHT + self.rows.len() as f32 * Self::ROW_HEIGHT + notes
+    }
+
+    // --- BREAKS A COLUMN TOO TALL FOR THE PAGE INTO PIECES THAT FIT, EACH UNDER A "(CONT.)" COPY OF THE HEADER ---
+    fn split(self, max_height: f32) -> Vec<Column> {
+        if self.height() <= max_height { return vec![self]; }
+
+        let mut pieces = Vec::new();
+        let mut piece  = Column { rows: Vec::new(), notes: Vec::new(), ..self.clone() };
+        let continued  = |header: &[HeaderLine]| {
+            let mut header = header.to_vec();
+            if let Some(first) = header.first_mut() { first.text.push_str(" (cont.)"); }
+            Column { header, rows: Vec::new(), notes: Vec::new(), style: self.style }
+        };
+
+        for row in self.rows {
+            if piece.height() + Self::ROW_HEIGHT > max_height && !piece.rows.is_empty() {
+                pieces.push(std::mem::replace(&mut piece, continued(&self.header)));
+            }
+            piece.rows.push(row);
+        }
+        for note in self.notes {
+            let grown = piece.height() + Self::NOTE_HEIGHT + if piece.notes.is_empty() { Self::NOTES_GAP } else { 0.0 };
+            if grown > max_height && !(piece.rows.is_empty() && piece.notes.is_empty()) {
+                pieces.push(std::mem::replace(&mut piece, continued(&self.header)));
+            }
+            piece.notes.push(note);
+        }
+        pieces.push(piece);
+        pieces
+    }
+
+    // --- DRAWS THE COLUMN WITH ITS TOP LEFT CORNER AT (x, top) ---
+    fn draw(&self, layer: &PdfLayerReference, x: f32, top: f32, width: f32, font: &IndirectFontRef, font_bold: &IndirectFontRef) {
+        let rect = |y: f32, height: f32| Polygon {
+            rings: vec![vec![
+                (Point::new(Mm(x), Mm(y)), false),
+                (Point::new(Mm(x + width), Mm(y)), false),
+                (Point::new(Mm(x + width), Mm(y + height)), false),
+                (Point::new(Mm(x), Mm(y + height)), false),
+            ]],
+            mode: PaintMode::FillStroke,
+            winding_order: WindingOrder::EvenOdd,
+        };
+        let pick = |bold: bool| if bold { font_bold } else { font };
+
+        // --- HEADER BOX ---
+        let header_y = top - Self::HEADER_HEIGHT;
+        layer.set_fill_color(rgb(self.style.header));
+        layer.set_outline_color(rgb(self.style.outline));
+        layer.set_outline_thickness(self.style.thickness);
+        layer.add_polygon(rect(header_y, Self::HEADER_HEIGHT));
+
+        layer.set_fill_color(black());
+        for line in &self.header {
+            layer.use_text(line.text.clone(), line.size, Mm(x + 2.0), Mm(header_y + line.y), pick(line.bold));
+        }
+
+        // --- ONE BOXED ROW PER PERSON ---
+        let mut y_cursor = header_y;
+        for row in &self.rows {
+            y_cursor -= Self::ROW_HEIGHT;
+
+            layer.set_fill_color(rgb(self.style.cell));
+            layer.set_outline_color(rgb(self.style.outline));
+            layer.add_polygon(rect(y_cursor, Self::ROW_HEIGHT));
+
+            layer.set_fill_color(black());
+            match row {
+                Row::Name(name) => layer.use_text(name.clone(), 10.0, Mm(x + 2.0), Mm(y_cursor + 2.0), font),
+                Row::Detailed(name, detail) => {
+                    layer.use_text(name.clone(), 9.0, Mm(x + 2.0), Mm(y_cursor + 3.5), font_bold);
+                    layer.use_text(detail.clone(), 6.0, Mm(x + 2.0), Mm(y_cursor + 0.8), font);
+                }
+            }
+        }
+        layer.set_outline_thickness(0.5);
+
+        // --- NOTES IN SMALL PRINT ---
+        y_cursor -= Self::NOTES_GAP;
+        for (text, bold) in &self.notes {
+            y_cursor -= Self::NOTE_HEIGHT;
+            layer.use_text(text.clone(), 8.0, Mm(x + 2.0), Mm(y_cursor + 1.0), pick(*bold));
+        }
+    }
+}
+
+// --- LAYS COLUMNS OUT LEFT TO RIGHT, WRAPPING ONTO A NEW ROW UNDER THE TALLEST ONE AND THEN ONTO A NEW PAGE ---
+// Nothing is ever drawn over anything else or off the bottom of the page
+pub struct SheetFlow {
+    pub pages: Pages,
+    section: String,
+    col_width: f32,
+    x: f32,                                 // Left edge of the next column
+    row_top: f32,                           // Top of the row being filled
+    row_bottom: f32,                        // Bottom of the tallest column in that row so far
+}
+
+impl SheetFlow {
+    const ROW_GAP: f32 = 6.0;
+
+    pub fn new(pages: Pages, section: &str, col_width: f32) -> Self {
+        let setup = pages.setup;
+        SheetFlow {
+            pages,
+            section: section.to_string(),
+            col_width,
+            x: setup.margin,
+            row_top: setup.content_top(),
+            row_bottom: setup.content_top(),
+        }
+    }
+
+    fn page_is_empty(&self) -> bool {
+        self.x == self.pages.setup.margin && self.row_top == self.pages.setup.content_top()
+    }
+
+    // --- STARTS A NEW SECTION (E.G. THE JOURNEYS HOME) ON A FRESH PAGE ---
+    pub fn section(&mut self, title: &str) {
+        self.section = title.to_string();
+        if !self.page_is_empty() {
+            self.next_page();
+        }
+    }
+
+    fn next_page(&mut self) {
+        self.pages.new_page(&self.section);
+        let setup       = self.pages.setup;
+        self.x          = setup.margin;
+        self.row_top    = setup.content_top();
+        self.row_bottom = setup.content_top();
+    }
+
+    pub fn place(&mut self, column: Column) {
+        let setup      = self.pages.setup;
+        let max_height = setup.content_top() - setup.content_bottom();
+
+        for piece in column.split(max_height) {
+            let height = piece.height();
+
+            // Across first, then down a row, then over the page
+            if self.x + self.col_width > setup.width - setup.margin + 0.01 {
+                self.x          = setup.margin;
+                self.row_top    = self.row_bottom - Self::ROW_GAP;
+                self.row_bottom = self.row_top;
+            }
+            if self.row_top - height < setup.content_bottom() {
+                self.next_page();
+            }
+
+            piece.draw(self.pages.layer(), self.x, self.row_top, self.col_width, &self.pages.font, &self.pages.font_bold);
+            self.row_bottom = self.row_bottom.min(self.row_top - height);
+            self.x += self.col_width;
+        }
+    }
+}
+
+// --- HELPERS ---
+pub fn rgb((r, g, b): (f32, f32, f32)) -> Color {
+    Color::Rgb(Rgb::new(r, g, b, None))
+}
+
+pub fn black() -> Color {
+    rgb((0.0, 0.0, 0.0))
+}
+
+// Rough width of Helvetica text, for lining things up against the right margin
+pub fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * 0.5 * 0.3528
+}
+
+// --- BREAKS TEXT INTO LINES THAT FIT A WIDTH, SPLITTING BETWEEN WORDS ---
+pub fn wrap(text: &str, size: f32, width: f32) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let longer = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if text_width(&longer, size) > width && !line.is_empty() {
+            lines.push(std::mem::replace(&mut line, word.to_string()));
+        } else {
+            line = longer;
+        }
+    }
+    if !line.is_empty() { lines.push(line); }
+    lines
+}
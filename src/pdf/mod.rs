@@ -0,0 +1,263 @@
+mod layout;
+mod handouts;
+
+use printpdf::*;
+use chrono::{Local, NaiveDate};
+use crate::models::{TransportGroup, Venue, Leg, Boat, Person};
+use crate::models::edit::TransportPlan;
+use crate::models::expenses::{ExpenseReport, pounds};
+use crate::models::venue::{venue_label, venue_colour};
+use crate::models::theme::{PdfTheme, Orientation};
+use layout::{Pages, PageSetup, SheetFlow, Column, ColumnStyle, HeaderLine, Row};
+
+pub use handouts::{generate_driver_slips, generate_itinerary_pdf};
+
+// --- THE TRANSPORT SHEET: OUTBOUND VEHICLES FIRST, THEN THE JOURNEYS HOME AND THE CREWS, EACH ON A FRESH PAGE ---
+// Anyone a partial plan couldn't seat is shown in a highlighted box after the outbound vehicles, then anyone making their own way
+// and the support staff. Columns flow across and down as many pages as they need, on the paper and in the colours of the club's theme.
+// The header carries the day of the outing, which needn't be the day it's printed.
+pub fn generate_pdf(plan: &TransportPlan, boats: &[Boat], people: &[Person], venues: &[Venue], theme: &PdfTheme, on: NaiveDate, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let allocations = &plan.groups;
+    let date        = on.format("%A %-d %B %Y").to_string();
+    let [r, g, b]   = theme.outline;
+    let outline     = (r, g, b);
+
+    let pages    = Pages::new("Transport Sheet", PageSetup::new(theme.paper, theme.orientation), theme, &date, "Outbound")?;
+    let mut flow = SheetFlow::new(pages, "Outbound", theme.column_width());
+
+    // --- OUTBOUND VEHICLES ---
+    for group in allocations.iter().filter(|g| g.leg == Leg::Outbound) {
+        flow.place(vehicle_column(group, venues, outline));
+    }
+
+    // --- PEOPLE NOT IN ANY VEHICLE ---
+    if !plan.needs_transport.is_empty() {
+        let rows: Vec<(String, String)> = plan.needs_transport.iter()
+            .flat_map(|s| s.people.iter().map(move |p| (p.name.clone(), format!("{} {}", s.departure_time, s.venue_name))))
+            .collect();
+        let style = ColumnStyle {
+            header: (1.0, 0.75, 0.0),               // Amber with a red outline so it stands out from the venue colours
+            cell: (1.0, 0.95, 0.75),
+            outline: (0.8, 0.0, 0.0),
+            thickness: 1.5,
+        };
+        flow.place(list_column("Needs transport", format!("{} not on a vehicle", rows.len()), "Sort out by message", rows, style));
+    }
+    if !plan.making_own_way.is_empty() {
+        let rows: Vec<(String, String)> = plan.making_own_way.iter()
+            .map(|o| (o.person.name.clone(), format!("{} {}", o.departure_time, o.venue_name)))
+            .collect();
+        let style = ColumnStyle { header: (0.8, 0.8, 0.8), cell: (1.0, 1.0, 1.0), outline, thickness: 1.5 };
+        flow.place(list_column("Making own way", format!("{} travelling privately", rows.len()), "Don't wait for them", rows, style));
+    }
+    if !plan.support_staff.is_empty() {
+        let rows: Vec<(String, String)> = plan.support_staff.iter()
+            .map(|s| {
+                let vehicle = plan.outbound_vehicle(&s.person.student_id).map_or("no vehicle", |g| g.vehicle_label.as_str());
+                (format!("{} ({})", s.person.name, s.role.label()), format!("{} {} - {}", s.departure_time, s.venue_name, vehicle))
+            })
+            .collect();
+        let style = ColumnStyle { header: (0.75, 0.85, 1.0), cell: (1.0, 1.0, 1.0), outline, thickness: 1.5 };
+        flow.place(list_column("Support staff", format!("{} off the water", rows.len()), "Coaches, launches and spares", rows, style));
+    }
+
+    // --- JOURNEYS HOME ---
+    let mut returns = allocations.iter().filter(|g| g.leg == Leg::Return).peekable();
+    if returns.peek().is_some() {
+        flow.section("Return");
+        for group in returns {
+            flow.place(vehicle_column(group, venues, outline));
+        }
+    }
+
+    // --- CREWS, SO COACHES DON'T NEED A SEPARATE LIST ---
+    if !boats.is_empty() {
+        flow.section("Crews");
+        for boat in boats {
+            flow.place(crew_column(boat, people, plan, venues, outline));
+        }
+    }
+
+    flow.pages.save(filename)
+}
+
+// --- ONE BOAT: ITS TYPE, DEPARTURE AND VENUE, THEN A ROW PER SEAT FROM BOW TO COX WITH THE VEHICLE EACH ROWER IS IN ---
+fn crew_column(boat: &Boat, people: &[Person], plan: &TransportPlan, venues: &[Venue], outline: (f32, f32, f32)) -> Column {
+    let venue = boat.destination.as_ref().map_or("No venue", |d| venue_label(venues, d));
+    let home  = if boat.return_time.trim().is_empty() { String::new() } else { format!("Ready to leave {}", boat.return_time.trim()) };
+
+    let rows = boat.seats.iter().enumerate()
+        .map(|(s_idx, seat)| {
+            let seat_label = boat.boat_type.seat_label(s_idx);
+            let Some(id) = seat else { return Row::Detailed(format!("{}  (empty)", seat_label), String::new()) };
+            let name = people.iter().find(|p| &p.student_id == id).map_or(id.as_str(), |p| p.name.as_str());
+            Row::Detailed(format!("{}  {}", seat_label, name), travelling_in(plan, boat, id))
+        })
+        .collect();
+
+    let mut header = vec![
+        HeaderLine::new(format!("{}  {}", boat.boat_type.label(), boat.departure_time), 10.0, true, 15.0),
+        HeaderLine::new(venue, 10.0, false, 11.0),
+        HeaderLine::new(home, 8.0, false, 6.0),
+        HeaderLine::new(format!("{} seats", boat.seats.len()), 8.0, false, 2.0),
+    ];
+    header.retain(|line| !line.text.is_empty());
+
+    Column {
+        header,
+        rows,
+        notes: Vec::new(),
+        style: ColumnStyle {
+            header: boat.destination.as_ref().map_or((1.0, 1.0, 1.0), |d| venue_colour(venues, d)),
+            cell: (1.0, 1.0, 1.0),
+            outline,
+            thickness: 0.5,
+        },
+    }
+}
+
+// --- HOW A ROWER GETS THERE: THEIR VEHICLE (AND WHETHER THEY'RE DRIVING IT), OR WHY THEY HAVE NONE ---
+fn travelling_in(plan: &TransportPlan, boat: &Boat, student_id: &str) -> String {
+    if boat.is_own_transport(student_id) || plan.making_own_way.iter().any(|o| o.person.student_id == student_id) {
+        return "Making own way".to_string();
+    }
+    match plan.outbound_vehicle(student_id) {
+        Some(g) if g.driver.student_id == student_id => format!("Driving {}", g.vehicle_label),
+        Some(g) => g.vehicle_label.clone(),
+        None if plan.needs_transport.iter().any(|s| s.people.iter().any(|p| p.student_id == student_id)) => "Needs transport".to_string(),
+        None => "Not on a vehicle".to_string(),
+    }
+}
+
+// --- ONE VEHICLE: VENUE-COLOURED HEADER, A ROW PER PASSENGER, THEN ITS LOAD, STOPS (OR DROP-OFFS) AND ARRIVAL ---
+fn vehicle_column(group: &TransportGroup, venues: &[Venue], outline: (f32, f32, f32)) -> Column {
+    let venue = match group.leg {
+        Leg::Outbound => venue_label(venues, &group.destination).to_string(),
+        Leg::Return => format!("From {}", venue_label(venues, &group.destination)),
+    };
+
+    let mut notes = Vec::new();
+    if !group.equipment.is_empty() {
+        notes.push((format!("Carrying {}", group.load_summary()), true));
+    }
+    for stop in &group.stops {
+        notes.push((format!("{}  {} ({})", stop.time, stop.location, stop.people.len()), false));
+    }
+    if !group.arrival_time.is_empty() {
+        let label = if group.leg == Leg::Return { "Back" } else { "Arrive" };
+        notes.push((format!("{} {}", label, group.arrival_time), true));
+    }
+
+    Column {
+        header: vec![
+            HeaderLine::new(group.departure_time.clone(), 10.0, true, 15.0),
+            HeaderLine::new(venue, 10.0, false, 11.0),
+            HeaderLine::new(format!("Driver: {}", group.driver.name), 9.0, true, 6.0),
+            HeaderLine::new(group.vehicle_label.clone(), 8.0, false, 2.0),
+        ],
+        rows: group.passengers.iter().map(|p| Row::Name(p.name.clone())).collect(),
+        notes,
+        style: ColumnStyle { header: venue_colour(venues, &group.destination), cell: (1.0, 1.0, 1.0), outline, thickness: 0.5 },
+    }
+}
+
+// --- A COLUMN OF PEOPLE LISTED APART FROM THE VEHICLES (NEEDS TRANSPORT, MAKING OWN WAY, SUPPORT STAFF) ---
+// Each row is a name and, underneath in small print, the departure they belong to
+fn list_column(title: &str, subtitle: String, note: &str, rows: Vec<(String, String)>, style: ColumnStyle) -> Column {
+    Column {
+        header: vec![
+            HeaderLine::new(title, 11.0, true, 14.0),
+            HeaderLine::new(subtitle, 9.0, false, 8.0),
+            HeaderLine::new(note, 8.0, false, 3.0),
+        ],
+        rows: rows.into_iter().map(|(name, detail)| Row::Detailed(name, detail)).collect(),
+        notes: Vec::new(),
+        style,
+    }
+}
+
+// --- MILEAGE CLAIMS FOR THE TREASURER: ONE BLOCK PER DRIVER, A LINE PER TRIP, CARRYING ON OVER AS MANY PAGES AS NEEDED ---
+pub fn generate_expense_pdf(report: &ExpenseReport, theme: &PdfTheme, filename: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let date      = Local::now().format("%A %-d %B %Y").to_string();
+    let setup     = PageSetup::new(theme.paper, Orientation::Portrait);
+    let mut pages = Pages::new("Mileage Claims", setup, theme, &date, "Mileage claims")?;
+    let (font, font_bold) = (pages.font.clone(), pages.font_bold.clone());
+    let rates     = &report.rates;
+
+    // --- LAYOUT SETTINGS ---
+    let setup      = pages.setup;
+    let top        = Mm(setup.content_top() - 8.0);
+    let bottom     = Mm(setup.content_bottom() + 4.0);
+    let row_height = Mm(5.0);
+    let columns    = [10.0, 32.0, 45.0, 90.0, 160.0, 175.0, 187.0];      // Date, leg, vehicle, route, miles, passengers, amount
+
+    let mut layer    = pages.layer().clone();
+    let mut y_cursor = top;
+
+    layer.use_text(
+        format!(
+            "{} to {} - {}p a mile, plus {}p a mile for each passenger's own miles",
+            report.from, report.to, rates.per_mile, rates.per_passenger_mile,
+        ),
+        9.0, Mm(10.0), Mm(setup.content_top()), &font,
+    );
+
+    if report.claims.is_empty() {
+        layer.use_text("No car journeys were published in this period.", 10.0, Mm(10.0), y_cursor, &font);
+    }
+
+    for claim in &report.claims {
+
+        // A driver's heading never sits alone at the bottom of a page
+        if y_cursor.0 - row_height.0 * 3.0 < bottom.0 {
+            layer    = pages.new_page("Mileage claims (continued)").clone();
+            y_cursor = top;
+        }
+
+        // --- DRIVER HEADING WITH THEIR TOTALS ---
+        layer.use_text(format!("{} ({})", claim.name, claim.student_id), 11.0, Mm(10.0), y_cursor, &font_bold);
+        y_cursor -= row_height;
+        layer.use_text(
+            format!(
+                "{:.1} miles - mileage £{} + passengers £{} = £{}",
+                claim.miles(), pounds(claim.mileage(rates)), pounds(claim.supplement(rates)), pounds(claim.total(rates)),
+            ),
+            9.0, Mm(10.0), y_cursor, &font,
+        );
+        y_cursor -= row_height;
+
+        for (heading, x) in ["Date", "Leg", "Vehicle", "Route", "Miles", "Pass.", "Claim"].iter().zip(columns) {
+            layer.use_text(*heading, 8.0, Mm(x), y_cursor, &font_bold);
+        }
+
+        // --- ONE LINE PER TRIP ---
+        for trip in &claim.trips {
+            y_cursor -= row_height;
+            if y_cursor.0 < bottom.0 {
+                layer    = pages.new_page("Mileage claims (continued)").clone();
+                y_cursor = top;
+            }
+
+            let leg   = if trip.leg == Leg::Return { "Return" } else { "Out" };
+            let route = if trip.route.chars().count() > 45 { format!("{}...", trip.route.chars().take(42).collect::<String>()) } else { trip.route.clone() };
+            let cells = [
+                trip.date.clone(), leg.to_string(), trip.vehicle_label.clone(), route,
+                format!("{:.1}", trip.miles), trip.passengers.to_string(),
+                format!("£{}", pounds(trip.mileage(rates) + trip.supplement(rates))),
+            ];
+            for (text, x) in cells.into_iter().zip(columns) {
+                layer.use_text(text, 8.0, Mm(x), y_cursor, &font);
+            }
+        }
+        y_cursor -= row_height * 2.0;
+    }
+
+    // --- WHAT THE CLUB OWES ALTOGETHER ---
+    if y_cursor.0 < bottom.0 {
+        layer    = pages.new_page("Mileage claims (continued)").clone();
+        y_cursor = top;
+    }
+    layer.use_text(format!("Total to pay: £{}", pounds(report.total())), 11.0, Mm(10.0), y_cursor, &font_bold);
+
+    pages.save(filename)
+}
@@ -1,37 +1,135 @@
 use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
 use std::fs::File;
 use std::io::{self, Write, BufReader};
+use std::collections::HashSet;
+use std::path::Path;
+use chrono::NaiveDate;
 
-use crate::models::{Person, Minibus};
+use crate::models::{Person, Minibus, Boat, Destination, Session, Venue, PickupPoint, TravelMatrix, TravelTime, TransportGroup};
+use crate::models::constraints::Constraint;
+use crate::models::edit::{OwnWay, SupportTrip};
+use crate::models::venue::venue_label;
+use crate::models::credentials::upgrade_legacy_flags;
+use crate::models::history::DriverHistory;
+use crate::models::theme::PdfTheme;
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct SystemState {
     pub people: Vec<Person>,
     pub minibuses: Vec<Minibus>,
+    pub venues: Vec<Venue>,
+    pub pickup_points: Vec<PickupPoint>,
+    pub travel_times: Vec<TravelTime>,
+    #[serde(skip)]
+    pub session: Session,                   // The crew plan currently on the lake
+    #[serde(skip)]
+    pub session_file: Option<String>,       // Where the current session was last opened from / saved to
+    #[serde(skip)]
+    pub history: DriverHistory,             // Who drove what on every published sheet (for the fair rota and driving stats)
+    #[serde(skip)]
+    pub theme: PdfTheme,                    // Club name, crest, colours, fonts and paper for the PDFs
+    #[serde(skip)]
+    unreadable: HashSet<String>,            // Files that were there but couldn't be read - never saved over
 }
 
+// --- A SESSION FILE IS EITHER A FULL SESSION OR (FOR OLDER CREW FILES) JUST A LIST OF BOATS ---
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SessionFile {
+    Session(Session),
+    Boats(Vec<Boat>),
+}
+
+type Loader = fn(&mut SystemState, &str) -> io::Result<()>;
+type Saver  = fn(&SystemState, &str) -> io::Result<()>;
+
 impl SystemState {
     const PEOPLE_FILE: &'static str = "people.json";
     const MINIBUSES_FILE: &'static str = "minibuses.json";
+    const VENUES_FILE: &'static str = "venues.json";
+    const PICKUP_POINTS_FILE: &'static str = "pickup_points.json";
+    const TRAVEL_TIMES_FILE: &'static str = "travel_times.json";
+    const HISTORY_FILE: &'static str = "driver_history.json";
+    const THEME_FILE: &'static str = "pdf_theme.json";
+    pub const SESSIONS_DIR: &'static str = "sessions";
 
-    pub fn load_all(&mut self) -> io::Result<()> {
-        self.load_people(Self::PEOPLE_FILE)?;
-        self.load_minibuses(Self::MINIBUSES_FILE)?;
-        Ok(())
+    // --- LOADS EVERY FILE IT CAN, AND REPORTS THE ONES IT COULDN'T READ (WHICH ARE THEN LEFT ALONE BY save_all) ---
+    pub fn load_all(&mut self) -> Result<(), String> {
+        let loaders: [(&str, Loader); 7] = [
+            (Self::PEOPLE_FILE, Self::load_people),
+            (Self::MINIBUSES_FILE, Self::load_minibuses),
+            (Self::VENUES_FILE, Self::load_venues),
+            (Self::PICKUP_POINTS_FILE, Self::load_pickup_points),
+            (Self::TRAVEL_TIMES_FILE, Self::load_travel_times),
+            (Self::HISTORY_FILE, Self::load_history),
+            (Self::THEME_FILE, Self::load_theme),
+        ];
+
+        let mut errors = Vec::new();
+        for (filename, load) in loaders {
+            if let Err(e) = load(self, filename) {
+                self.unreadable.insert(filename.to_string());
+                errors.push(format!("Could not read {}: {}", filename, e));
+            }
+        }
+        if errors.is_empty() {
+            return Ok(());
+        }
+        // The theme is only ever read, so there's nothing to hold back for it alone
+        if self.unreadable.iter().any(|f| f != Self::THEME_FILE) {
+            errors.push("Nothing will be saved over these files until they are fixed and the app is restarted.".to_string());
+        }
+        Err(errors.join("\n"))
     }
 
     pub fn save_all(&self) -> io::Result<()> {
-        self.save_people(Self::PEOPLE_FILE)?;
-        self.save_minibuses(Self::MINIBUSES_FILE)?;
+        let savers: [(&str, Saver); 4] = [
+            (Self::PEOPLE_FILE, Self::save_people),
+            (Self::MINIBUSES_FILE, Self::save_minibuses),
+            (Self::VENUES_FILE, Self::save_venues),
+            (Self::PICKUP_POINTS_FILE, Self::save_pickup_points),
+        ];
+
+        // One unreadable file shouldn't stop the others being saved
+        let mut result = Ok(());
+        for (filename, save) in savers {
+            if let Err(e) = self.check_writable(filename).and_then(|_| save(self, filename)) {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    // --- REFUSES TO WRITE A FILE THAT FAILED TO LOAD, SO WHATEVER DIDN'T PARSE ISN'T LOST ---
+    fn check_writable(&self, filename: &str) -> io::Result<()> {
+        if self.unreadable.contains(filename) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} could not be read when the app started, so it was not saved over", filename)));
+        }
         Ok(())
     }
 
+    // --- READS A WHOLE FILE - None WHEN IT DOESN'T EXIST YET, AN ERROR WHEN IT'S THERE BUT DOESN'T PARSE ---
+    fn read_json<T: DeserializeOwned>(filename: &str) -> io::Result<Option<T>> {
+        let file = match File::open(filename) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        serde_json::from_reader(BufReader::new(file)).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
     pub fn load_people(&mut self, filename: &str) -> io::Result<()> {
-        if let Ok(file) = File::open(filename) {
-            let reader  = BufReader::new(file);
-            self.people = serde_json::from_reader(reader).unwrap_or_default();
-        } else {
-            self.people = Vec::new();
+        self.people = Vec::new();
+        let Some(mut raw) = Self::read_json::<Vec<serde_json::Value>>(filename)? else { return Ok(()) };
+
+        // Older files carried yes/no driving flags instead of credentials, so those are converted on the way in
+        raw.iter_mut().for_each(upgrade_legacy_flags);
+        for (i, record) in raw.into_iter().enumerate() {
+            let id = record.get("student_id").and_then(|v| v.as_str()).unwrap_or("?").to_string();
+            let person = serde_json::from_value(record)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("person {} (student ID '{}'): {}", i + 1, id, e)))?;
+            self.people.push(person);
         }
         Ok(())
     }
@@ -43,12 +141,7 @@ impl SystemState {
     }
 
     pub fn load_minibuses(&mut self, filename: &str) -> io::Result<()> {
-        if let Ok(file)    = File::open(filename) {
-            let reader     = BufReader::new(file);
-            self.minibuses = serde_json::from_reader(reader).unwrap_or_default();
-        } else {
-            self.minibuses = Vec::new();
-        }
+        self.minibuses = Self::read_json(filename)?.unwrap_or_default();
         Ok(())
     }
 
@@ -58,7 +151,306 @@ impl SystemState {
         file.write_all(data.as_bytes())
     }
 
+    // --- VENUES FALL BACK TO THE ORIGINAL TWO WHEN THERE IS NO venues.json YET ---
+    pub fn load_venues(&mut self, filename: &str) -> io::Result<()> {
+        self.venues = Self::read_json(filename)?.unwrap_or_else(Venue::defaults);
+        Ok(())
+    }
+
+    pub fn save_venues(&self, filename: &str) -> io::Result<()> {
+        let data     = serde_json::to_string_pretty(&self.venues).unwrap();
+        let mut file = File::create(filename)?;
+        file.write_all(data.as_bytes())
+    }
+
+    // --- WITHOUT A pickup_points.json, SEED THE LIST FROM THE NAMES ALREADY USED BY PEOPLE AND MINIBUSES (MUST LOAD AFTER THEM) ---
+    pub fn load_pickup_points(&mut self, filename: &str) -> io::Result<()> {
+        if let Some(points) = Self::read_json(filename)? {
+            self.pickup_points = points;
+        } else {
+            let mut names: Vec<&String> = self.people.iter().flat_map(|p| p.pickup_locations.iter())
+                .chain(self.minibuses.iter().map(|m| &m.depot))
+                .collect();
+            names.sort();
+            names.dedup();
+            self.pickup_points = names.into_iter().map(|n| PickupPoint::named(n)).collect();
+        }
+        Ok(())
+    }
+
+    pub fn save_pickup_points(&self, filename: &str) -> io::Result<()> {
+        let data     = serde_json::to_string_pretty(&self.pickup_points).unwrap();
+        let mut file = File::create(filename)?;
+        file.write_all(data.as_bytes())
+    }
+
+    // --- MEASURED DRIVE TIMES ARE OPTIONAL - WITHOUT THEM ROUTES ARE ESTIMATED FROM COORDINATES ---
+    pub fn load_travel_times(&mut self, filename: &str) -> io::Result<()> {
+        self.travel_times = Self::read_json(filename)?.unwrap_or_default();
+        Ok(())
+    }
+
+    // --- THE CLUB'S LOOK FOR THE PDFS IS OPTIONAL - WITHOUT IT THEY USE THE PLAIN BLACK AND WHITE DEFAULTS ---
+    // A file that's there but doesn't parse is reported rather than quietly printing unbranded sheets
+    pub fn load_theme(&mut self, filename: &str) -> io::Result<()> {
+        self.theme = Self::read_json(filename)?.unwrap_or_default();
+        Ok(())
+    }
+
+    pub fn load_history(&mut self, filename: &str) -> io::Result<()> {
+        self.history = Self::read_json(filename)?.unwrap_or_default();
+        Ok(())
+    }
+
+    // --- ADDS A PUBLISHED PLAN'S DRIVERS TO THE HISTORY (DATED BY THE OUTING, UNDER THE CURRENT SESSION'S NAME) AND SAVES IT ---
+    // `on` is the outing date the plan was made for; the session itself is left alone, so a reused session file keeps no date
+    pub fn record_drives(&mut self, groups: &[TransportGroup], on: NaiveDate) -> io::Result<()> {
+        self.check_writable(Self::HISTORY_FILE)?;
+        self.history.record(groups, on, &self.session.name);
+        let data     = serde_json::to_string_pretty(&self.history).unwrap();
+        let mut file = File::create(Self::HISTORY_FILE)?;
+        file.write_all(data.as_bytes())
+    }
+
+    pub fn travel_matrix(&self) -> TravelMatrix<'_> {
+        TravelMatrix {
+            pickup_points: &self.pickup_points,
+            venues: &self.venues,
+            travel_times: &self.travel_times,
+        }
+    }
+
+    pub fn is_pickup_point(&self, name: &str) -> bool {
+        self.pickup_points.iter().any(|p| p.name == name)
+    }
+
+    // --- CHECKS A SET OF PEOPLE (AND EVERY MINIBUS DEPOT) ONLY REFERENCE KNOWN PICKUP POINTS ---
+    pub fn validate_pickups<'a>(&self, people: impl IntoIterator<Item = &'a Person>) -> Result<(), String> {
+        for person in people {
+            if let Some(loc) = person.pickup_locations.iter().find(|l| !self.is_pickup_point(l)) {
+                return Err(format!("Error: {} has unknown pickup point '{}'.", person.name, loc));
+            }
+        }
+        if let Some(mb) = self.minibuses.iter().find(|m| !self.is_pickup_point(&m.depot)) {
+            return Err(format!("Error: Minibus {} has unknown depot '{}'.", mb.registration, mb.depot));
+        }
+        Ok(())
+    }
+
     pub fn get_person(&self, id: &str) -> Option<&Person> {
         self.people.iter().find(|p| p.student_id == id)
     }
+
+    // --- READS A SESSION FILE - UNLIKE THE SQUAD FILES A MISSING SESSION FILE IS AN ERROR ---
+    pub fn read_session(filename: &str) -> io::Result<Session> {
+        let reader = BufReader::new(File::open(filename)?);
+        let file: SessionFile = serde_json::from_reader(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(match file {
+            SessionFile::Session(session) => session,
+            SessionFile::Boats(boats) => Session {                                      // Bare crew lists are named after their file
+                name: Path::new(filename).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default(),
+                boats,
+                ..Default::default()
+            },
+        })
+    }
+
+    pub fn load_session(&mut self, filename: &str) -> io::Result<()> {
+        self.session      = Self::read_session(filename)?;
+        self.session_file = Some(filename.to_string());
+        Ok(())
+    }
+
+    pub fn save_session(&mut self, filename: &str) -> io::Result<()> {
+        if let Some(dir) = Path::new(filename).parent() {
+            std::fs::create_dir_all(dir)?;                                              // Creates the sessions folder on first save
+        }
+        let data     = serde_json::to_string_pretty(&self.session).unwrap();
+        let mut file = File::create(filename)?;
+        file.write_all(data.as_bytes())?;
+        self.session_file = Some(filename.to_string());
+        Ok(())
+    }
+
+    // --- LISTS THE SAVED SESSION FILES (SORTED BY NAME) FOR THE OPEN SESSION WINDOW ---
+    pub fn list_sessions() -> Vec<String> {
+        let mut files: Vec<String> = std::fs::read_dir(Self::SESSIONS_DIR)
+            .map(|entries| entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect())
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+
+    // --- BUILDS THE FILE PATH A SESSION OF THE GIVEN NAME IS SAVED TO ---
+    pub fn session_path(name: &str) -> String {
+        let file_name: String = name.trim().chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        format!("{}/{}.json", Self::SESSIONS_DIR, file_name)
+    }
+
+    // --- ERROR CHECK FOR MISSING DEPARTURE TIMES, DESTINATIONS, UNFILLED SEATS OR UNKNOWN ROWERS ---
+    pub fn validate_boats(&self, boats: &[Boat]) -> Result<(), String> {
+        // A mistyped outing date would quietly check the drivers against today instead
+        self.session.validate_date().map_err(|msg| format!("Error: {}", msg))?;
+        if boats.iter().any(|b| b.departure_time.trim().is_empty()) {
+            return Err("Error: All boat departure times must be entered.".to_string());
+        }
+        if let Some(b) = boats.iter().find(|b| chrono::NaiveTime::parse_from_str(b.departure_time.trim(), "%H:%M").is_err()) {
+            return Err(format!("Error: Departure time '{}' must be written as HH:MM (e.g. 07:00).", b.departure_time));
+        }
+
+        // Return times are optional, but must be readable and after the boat leaves
+        for b in boats.iter().filter(|b| !b.return_time.trim().is_empty()) {
+            let (Ok(out), Ok(back)) = (
+                chrono::NaiveTime::parse_from_str(b.departure_time.trim(), "%H:%M"),
+                chrono::NaiveTime::parse_from_str(b.return_time.trim(), "%H:%M"),
+            ) else {
+                return Err(format!("Error: Return time '{}' must be written as HH:MM (e.g. 09:30).", b.return_time));
+            };
+            if back <= out {
+                return Err(format!("Error: Return time {} is not after the departure time {}.", b.return_time, b.departure_time));
+            }
+        }
+        if let Some(drop_off) = boats.iter().filter_map(|b| b.drop_off.as_ref()).find(|d| !self.is_pickup_point(d)) {
+            return Err(format!("Error: Unknown drop-off point '{}'.", drop_off));
+        }
+        if boats.iter().any(|b| b.destination.is_none()) {
+            return Err("Error: All destination locations must be selected.".to_string());
+        }
+        if let Some(dest) = boats.iter().filter_map(|b| b.destination.as_ref()).find(|d| crate::models::venue::find_venue(&self.venues, d).is_none()) {
+            return Err(format!("Error: Unknown venue '{}' - add it to the venues list first.", dest.0));
+        }
+        if boats.iter().any(|b| b.seats.len() != b.boat_type.seat_count() || b.seats.iter().any(|s| s.is_none())) {
+            return Err("Error: All boat seats must be filled.".to_string());
+        }
+
+        // Every seated ID must exist in the squad and only be seated once across the whole lake
+        let mut seen = HashSet::new();
+        for id in boats.iter().flat_map(|b| b.seats.iter().flatten()) {
+            if self.get_person(id).is_none() {
+                return Err(format!("Error: Unknown student ID '{}' in crew.", id));
+            }
+            if !seen.insert(id) {
+                return Err(format!("Error: '{}' is seated in more than one boat.", id));
+            }
+        }
+
+
+        // Support staff need a departure of their own and can't also be in a boat
+        for staff in &self.session.support {
+            let Some(person) = self.get_person(&staff.student_id) else {
+                return Err(format!("Error: Unknown student ID '{}' in support staff.", staff.student_id));
+            };
+            if !seen.insert(&staff.student_id) {
+                return Err(format!("Error: {} is in the support staff tray and seated in a boat.", person.name));
+            }
+            if chrono::NaiveTime::parse_from_str(staff.departure_time.trim(), "%H:%M").is_err() {
+                return Err(format!("Error: {}'s support staff time '{}' must be written as HH:MM (e.g. 07:00).", person.name, staff.departure_time));
+            }
+            match &staff.destination {
+                None => return Err(format!("Error: Select a destination for {} in the support staff tray.", person.name)),
+                Some(dest) if crate::models::venue::find_venue(&self.venues, dest).is_none() => {
+                    return Err(format!("Error: Unknown venue '{}' - add it to the venues list first.", dest.0));
+                }
+                Some(_) => {}
+            }
+        }
+
+        // Equipment has to name a departure that can be found, and at least one of the thing
+        for need in &self.session.equipment {
+            let what = need.kind.describe(need.quantity as usize);
+            if need.quantity == 0 {
+                return Err(format!("Error: Equipment for {} {} needs a quantity of at least 1.", need.departure_time, need.destination.0));
+            }
+            if chrono::NaiveTime::parse_from_str(need.departure_time.trim(), "%H:%M").is_err() {
+                return Err(format!("Error: Equipment time '{}' for the {} must be written as HH:MM (e.g. 07:00).", need.departure_time, what));
+            }
+            if crate::models::venue::find_venue(&self.venues, &need.destination).is_none() {
+                return Err(format!("Error: Unknown venue '{}' for the {} - add it to the venues list first.", need.destination.0, what));
+            }
+        }
+
+        // Typos in pickup points would silently break the location matching in the allocation
+        self.validate_pickups(seen.into_iter().filter_map(|id| self.get_person(id)))
+    }
+
+    // --- EVERYONE ON THE LAKE MAKING THEIR OWN WAY (SEATS MARKED OWN TRANSPORT, OR "TRAVELS PRIVATELY" IN THE SESSION) ---
+    pub fn making_own_way(&self, boats: &[Boat]) -> Vec<OwnWay> {
+        let private: HashSet<&str> = self.session.constraints.iter()
+            .filter_map(|c| if let Constraint::TravelsPrivately(id) = c { Some(id.as_str()) } else { None })
+            .collect();
+
+        let mut own_way = Vec::new();
+        for boat in boats {
+            let Some(dest) = &boat.destination else { continue };
+            for id in boat.seats.iter().flatten() {
+                if !boat.is_own_transport(id) && !private.contains(id.as_str()) { continue; }
+                if let Some(person) = self.get_person(id) {
+                    own_way.push(OwnWay {
+                        person: person.clone(),
+                        departure_time: boat.departure_time.clone(),
+                        venue_name: venue_label(&self.venues, dest).to_string(),
+                    });
+                }
+            }
+        }
+        own_way
+    }
+
+    // --- EVERYONE IN THE SUPPORT STAFF TRAY, FOR LISTING SEPARATELY ON THE TRANSPORT SHEET ---
+    pub fn support_trips(&self) -> Vec<SupportTrip> {
+        self.session.support.iter()
+            .filter_map(|staff| Some(SupportTrip {
+                person: self.get_person(&staff.student_id)?.clone(),
+                role: staff.role,
+                departure_time: staff.departure_time.trim().to_string(),
+                venue_name: venue_label(&self.venues, staff.destination.as_ref()?).to_string(),
+            }))
+            .collect()
+    }
+
+    // --- GROUPS SEATED PEOPLE BY DESTINATION AND DEPARTURE TIME READY FOR THE ALLOCATION ALGORITHM ---
+    pub fn transport_requests(&self, boats: &[Boat]) -> Vec<(Destination, String, Vec<Person>)> {
+        let mut groups: Vec<(Destination, String, Vec<Person>)> = Vec::new();
+
+        for boat in boats {
+            let Some(dest) = boat.destination.clone() else { continue };        // Boats without a venue can't be transported anywhere
+            let time = boat.departure_time.clone();
+
+            let people: Vec<Person> = boat.seats.iter().flatten()
+                .filter(|id| !boat.is_own_transport(id))                        // People making their own way stay in the crew but need no seat
+                .filter_map(|id| self.get_person(id).cloned())
+                .collect();
+
+            if let Some(existing) = groups.iter_mut().find(|(d, t, _)| *d == dest && *t == time) {
+                existing.2.extend(people);
+            } else {
+                groups.push((dest, time, people));
+            }
+        }
+
+        // --- SUPPORT STAFF JOIN THEIR DEPARTURE LIKE ANY ROWER, PREFERRED DRIVERS AT THE FRONT ---
+        for staff in &self.session.support {
+            let (Some(dest), Some(person)) = (staff.destination.clone(), self.get_person(&staff.student_id).cloned()) else { continue };
+            let time = staff.departure_time.trim().to_string();
+
+            let idx = match groups.iter().position(|(d, t, _)| *d == dest && *t == time) {
+                Some(idx) => idx,
+                None => { groups.push((dest, time, Vec::new())); groups.len() - 1 }
+            };
+            if staff.prefers_driving {
+                groups[idx].2.insert(0, person);
+            } else {
+                groups[idx].2.push(person);
+            }
+        }
+        groups
+    }
 }
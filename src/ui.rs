@@ -1,53 +1,15 @@
 use eframe::egui;
 use chrono::Local;
-use crate::models::{Person, Car, Allocation, Minibus, Destination, Gender};
+use crate::models::{Person, Minibus, Gender, Boat, BoatType, Venue, PickupPoint, StrategyKind, Lock, Leg, Destination, SupportStaff, SupportRole};
+use crate::models::edit::TransportPlan;
+use crate::models::constraints::ConstraintKind;
+use crate::models::equipment::{Haulage, EquipmentKind, EquipmentNeed};
+use crate::models::credentials::{DriverCredentials, LicenceCategory};
+use crate::models::expenses::{ExpenseReport, ExpenseRates, parse_range, pounds};
+use crate::models::itinerary::itineraries;
+use crate::models::venue::venue_label;
 use crate::state::SystemState;
-
-// --- CREATING A SIMPLE ENUM FOR DIFFERENT BOAT CLASSIFICATIONS ---
-#[derive(Clone, Copy, PartialEq)]
-pub enum BoatType {
-    Single, 
-    Double, 
-    Pair, 
-    Quad, 
-    Four, 
-    FourCoxed, 
-    EightCoxed,
-}
-
-// --- IMPLEMENTING METHODS TO RETURN SEAT COUNTS AND LABELS TO BE DISPLAYED FOR DIFFERENT BOAT TYPES ---
-impl BoatType {
-    fn seat_count(&self) -> usize {
-        match self {
-            BoatType::Single => 1,
-            BoatType::Double | BoatType::Pair => 2,
-            BoatType::Quad | BoatType::Four => 4,
-            BoatType::FourCoxed => 5,
-            BoatType::EightCoxed => 9,
-        }
-    }
-
-    fn label(&self) -> &str {
-        match self {
-            BoatType::Single => "1x",
-            BoatType::Double => "2x",
-            BoatType::Pair => "2-",
-            BoatType::Quad => "4x-",
-            BoatType::Four => "4-",
-            BoatType::FourCoxed => "4+",
-            BoatType::EightCoxed => "8+",
-        }
-    }
-}
-
-// --- DEFINING A STRUCTURE TO REPRESENT EACH BOAT ON THE LAKE (AND HANDLE UI STUFF (pos)) ---
-pub struct Boat {
-    pub boat_type: BoatType,
-    pub seats: Vec<Option<String>>,         // Each seat can be None (empty) or Some(student_id)
-    pub pos: egui::Pos2,                    // Position of the "boat" on the frame
-    pub departure_time: String,             // Departure time (e.g. "07:00")
-    pub destination: Option<Destination>,   // Venue for specific boat (e.g. auchenstarry or strathclyde)
-}
+use crate::export::ExportFormat;
 
 // --- FORM STATE FOR THE ADD PERSON POPUP WINDOW ---
 #[derive(Default)]
@@ -56,12 +18,15 @@ pub struct AddPersonForm {
     gender: Option<Gender>,
     student_id: String,
     year_of_entry: String,
-    pickup_locations: String,   // Comma-separated input
-    can_drive_minibus: bool,
+    pickup_locations: Vec<String>,  // Names of the ticked pickup points
+    phone: String,
+    credentials: DriverCredentials,
     has_car: bool,              // Whether the person owns a car
+    wants_to_drive: bool,       // Standing opt-in to drive their own car
     car_type: String,
     car_registration: String,
     car_seats: String,
+    car_kit: HaulageForm,       // Tow bar and boot space of their car
 }
 
 // --- FORM STATE FOR THE EDIT PERSON POPUP WINDOW ---
@@ -71,13 +36,17 @@ pub struct EditPersonForm {
     gender: Option<Gender>,
     student_id: String,
     year_of_entry: String,
-    pickup_locations: String,
-    can_drive_minibus: bool,
+    pickup_locations: Vec<String>,
+    phone: String,
+    credentials: DriverCredentials,
     has_car: bool,
-    wants_to_drive: bool,       // Whether the person has opted in to drive
+    wants_to_drive: bool,       // Standing opt-in to drive their own car (saved on the person)
+    session_drive: Option<bool>,// This session's override of the opt-in (None = use the standing default)
+    warnings: Vec<String>,      // Credential warnings for the person as saved
     car_type: String,
     car_registration: String,
     car_seats: String,
+    car_kit: HaulageForm,
 }
 
 // --- FORM STATE FOR THE EDIT MINIBUS POPUP WINDOW ---
@@ -85,27 +54,158 @@ pub struct EditMinibusForm {
     index: usize,               // Index into state.minibuses for in-place updates
     registration: String,
     seats: String,
+    depot: String,              // Pickup point the minibus lives at
+    kit: HaulageForm,
+}
+
+// --- FORM STATE FOR A VEHICLE'S TOW BAR AND BOOT SPACE (PART OF THE PERSON AND MINIBUS FORMS) ---
+#[derive(Default)]
+pub struct HaulageForm {
+    tow_capable: bool,
+    tow_bar_fitted: bool,
+    cargo_slots: String,
+}
+
+impl HaulageForm {
+    fn from(kit: &Haulage) -> Self {
+        HaulageForm { tow_capable: kit.tow_capable, tow_bar_fitted: kit.tow_bar_fitted, cargo_slots: kit.cargo_slots.to_string() }
+    }
+
+    // A blank or unreadable boot size is taken as no boot space
+    fn build(&self) -> Haulage {
+        Haulage { tow_capable: self.tow_capable, tow_bar_fitted: self.tow_bar_fitted, cargo_slots: self.cargo_slots.trim().parse().unwrap_or(0) }
+    }
+}
+
+// --- FORM STATE FOR THE EDIT PICKUP POINT POPUP WINDOW ---
+pub struct EditPickupForm {
+    index: Option<usize>,       // Index into state.pickup_points for in-place updates (None when adding a new point)
+    name: String,
+    latitude: String,
+    longitude: String,
+    notes: String,
+}
+
+// --- FORM STATE FOR THE EDIT VENUE POPUP WINDOW ---
+pub struct EditVenueForm {
+    index: Option<usize>,       // Index into state.venues for in-place updates (None when adding a new venue)
+    name: String,
+    colour: [f32; 3],
+    address: String,
+    latitude: String,
+    longitude: String,
+    travel_minutes: String,
+    session_minutes: String,
+}
+
+// --- FORM STATE FOR THE SESSION CONSTRAINTS POPUP WINDOW ---
+#[derive(Default)]
+pub struct ConstraintForm {
+    kind: ConstraintKind,       // Kind of constraint being added
+    people: Vec<String>,        // Student IDs ticked for it
+}
+
+// --- FORM STATE FOR THE SESSION EQUIPMENT POPUP WINDOW ---
+#[derive(Default)]
+pub struct EquipmentForm {
+    kind: EquipmentKind,
+    quantity: String,
+    departure_time: String,
+    destination: Option<Destination>,
+}
+
+impl EquipmentForm {
+    fn build(&self) -> Result<EquipmentNeed, String> {
+        let quantity: u8 = match self.quantity.trim() {
+            "" => 1,
+            text => text.parse().ok().filter(|&n| n > 0).ok_or("Quantity must be a whole number from 1 to 255.")?,
+        };
+        if chrono::NaiveTime::parse_from_str(self.departure_time.trim(), "%H:%M").is_err() {
+            return Err("Departure time must be written as HH:MM (e.g. 07:00).".to_string());
+        }
+        let destination = self.destination.clone().ok_or("Pick the venue it's going to.")?;
+        Ok(EquipmentNeed { kind: self.kind, quantity, destination, departure_time: self.departure_time.trim().to_string() })
+    }
+}
+
+// --- FORM STATE FOR THE MILEAGE CLAIMS POPUP WINDOW ---
+pub struct ExpenseForm {
+    from: String,               // Date range of published sheets to claim for (YYYY-MM-DD, inclusive)
+    to: String,
+    per_mile: String,           // Rates in pounds
+    per_passenger_mile: String,
+}
+
+impl Default for ExpenseForm {
+    // From the start of this month to today, at the standard rates
+    fn default() -> Self {
+        let today = Local::now().date_naive();
+        let rates = ExpenseRates::default();
+        ExpenseForm {
+            from:               today.format("%Y-%m-01").to_string(),
+            to:                 today.format("%Y-%m-%d").to_string(),
+            per_mile:           pounds(rates.per_mile),
+            per_passenger_mile: pounds(rates.per_passenger_mile),
+        }
+    }
+}
+
+impl ExpenseForm {
+    fn build(&self, state: &SystemState) -> Result<ExpenseReport, String> {
+        let (from, to) = parse_range(&self.from, &self.to)?;
+        let rates      = ExpenseRates::parse(&self.per_mile, &self.per_passenger_mile)?;
+        Ok(ExpenseReport::build(&state.history, from, to, rates, &state.travel_matrix()))
+    }
+}
+
+// --- SOMEONE BEING DRAGGED ONTO A VEHICLE CARD IN THE TRANSPORT VIEW ---
+#[derive(Clone, Copy)]
+pub enum Dragged {
+    Passenger(usize, usize),    // (group index, passenger index)
+    Stranded(usize, usize),     // (needs transport index, person index)
+}
+
+// --- A CHANGE MADE TO THE TRANSPORT PLAN BY HAND, APPLIED ONCE THE CARDS HAVE BEEN DRAWN ---
+enum PlanEdit {
+    Move(usize, usize, usize),  // (from group, passenger index, to group)
+    Seat(usize, usize, usize),  // (needs transport index, person index, to group)
+    Drive(usize, usize),        // (group, passenger index) - swaps them with the driver
+    ToggleLock(Lock),
 }
 
 // --- DEFINING THE MAIN APPLICATION STRUCTURE TO DESCRIBE THE SYSTEM ---
 pub struct RowingApp {
     pub state: SystemState,                                // Contains all the people and minibuses data loaded from JSON
-    pub boats: Vec<Boat>,                                  // Represents the boats currently on the frame and their assigned rowers
     pub selected_id: Option<String>,                       // Tracks which person (by student_id) is currently selected for training
     pub error_message: Option<String>,                     // Used to display error messages
     pub show_add_person: bool,                             // Controls whether the Add Person popup is open
     pub add_person_form: AddPersonForm,                    // Holds the in-progress input data for the Add Person form
     pub edit_person_form: Option<EditPersonForm>,          // Holds in-progress edits for a person
     pub edit_minibus_form: Option<EditMinibusForm>,        // Holds in-progress edits for a minibus
-    pub wants_to_drive: std::collections::HashSet<String>, // Tracks which person IDs have "Wants to Drive" checked
+    pub edit_venue_form: Option<EditVenueForm>,            // Holds in-progress edits for a venue (new or existing)
+    pub edit_pickup_form: Option<EditPickupForm>,          // Holds in-progress edits for a pickup point (new or existing)
+    pub save_as_name: Option<String>,                      // Holds the in-progress name while the Save As popup is open
+    pub show_open_session: bool,                           // Controls whether the Open Session popup is open
+    pub strategy: StrategyKind,                            // Which allocation strategy "Publish & PDF" uses
+    pub publish_partial: bool,                             // Publish whoever could be seated, listing the rest as needing transport
+    pub show_transport: bool,                              // Shows the Transport view (one card per vehicle) instead of the lake
+    pub transport_plan: Option<TransportPlan>,             // Latest allocation, edited by hand in the Transport view and printed from
+    pub dragging: Option<Dragged>,                         // Person being dragged between vehicle cards
+    pub constraint_form: Option<ConstraintForm>,           // Holds the in-progress constraint while the Constraints popup is open
+    pub equipment_form: Option<EquipmentForm>,             // Holds the in-progress equipment need while the Equipment popup is open
+    pub fair_rota: bool,                                   // Hand each vehicle to whoever on board has driven least recently
+    pub show_driving_stats: bool,                          // Controls whether the Driving Stats popup is open
+    pub expense_form: Option<ExpenseForm>,                 // Holds the date range and rates while the Expenses popup is open
+    pub export_format: ExportFormat,                       // Format the Transport tab's "Export" button writes
 }
 
 impl eframe::App for RowingApp {
 
     // --- THE MAIN UPDATE LOOP WHERE ALL THE UI LOGIC HAPPENS ---
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let assigned_ids: std::collections::HashSet<String> = self.boats.iter()
+        let assigned_ids: std::collections::HashSet<String> = self.state.session.boats.iter()
                                                                         .flat_map(|b| b.seats.iter().flatten().cloned())
+                                                                        .chain(self.state.session.support.iter().map(|s| s.student_id.clone()))     // Support staff count as assigned too
                                                                         .collect();
 
         // --- DISPLAY: ERROR WINDOW ---
@@ -141,8 +241,11 @@ impl eframe::App for RowingApp {
                     ui.label("Year of Entry:");
                     ui.text_edit_singleline(&mut self.add_person_form.year_of_entry);
 
-                    ui.label("Pickup Locations (comma-separated):");
-                    ui.text_edit_singleline(&mut self.add_person_form.pickup_locations);
+                    ui.label("Phone:");
+                    ui.text_edit_singleline(&mut self.add_person_form.phone);
+
+                    ui.label("Pickup Locations:");
+                    Self::pickup_picker(ui, &self.state.pickup_points, &mut self.add_person_form.pickup_locations);
 
                     ui.label("Gender:");
                     ui.horizontal(|ui| {
@@ -150,7 +253,8 @@ impl eframe::App for RowingApp {
                         ui.selectable_value(&mut self.add_person_form.gender, Some(Gender::Female), "Female");
                     });
 
-                    ui.checkbox(&mut self.add_person_form.can_drive_minibus, "Can drive minibus");
+                    ui.separator();
+                    Self::credentials_fields(ui, &mut self.add_person_form.credentials);
 
                     ui.separator();
 
@@ -166,6 +270,10 @@ impl eframe::App for RowingApp {
 
                         ui.label("Car Seats:");
                         ui.text_edit_singleline(&mut self.add_person_form.car_seats);
+                        Self::haulage_fields(ui, &mut self.add_person_form.car_kit);
+
+                        // "Wants to Drive" — only shown when the person has their own car
+                        ui.checkbox(&mut self.add_person_form.wants_to_drive, "Wants to Drive");
                     });
 
                     ui.add_space(8.0);
@@ -173,9 +281,11 @@ impl eframe::App for RowingApp {
                         if ui.button("Add Person").clicked() {
 
                             // Attempt to build and save the new person from form data
-                            if let Some(person) = Self::build_person_from_add_form(&self.add_person_form) {
+                            if let Err(e) = self.add_person_form.credentials.validate() {
+                                self.error_message = Some(e);
+                            } else if let Some(person) = Self::build_person_from_add_form(&self.add_person_form) {
                                 self.state.people.push(person);
-                                self.state.save_all().ok();
+                                self.save_squad();
 
                                 // Resets form and closes it
                                 self.add_person_form = AddPersonForm::default();
@@ -218,8 +328,11 @@ impl eframe::App for RowingApp {
                     ui.label("Year of Entry:");
                     ui.text_edit_singleline(&mut form.year_of_entry);
 
-                    ui.label("Pickup Locations (comma-separated):");
-                    ui.text_edit_singleline(&mut form.pickup_locations);
+                    ui.label("Phone:");
+                    ui.text_edit_singleline(&mut form.phone);
+
+                    ui.label("Pickup Locations:");
+                    Self::pickup_picker(ui, &self.state.pickup_points, &mut form.pickup_locations);
 
                     ui.label("Gender:");
                     ui.horizontal(|ui| {
@@ -227,7 +340,13 @@ impl eframe::App for RowingApp {
                         ui.selectable_value(&mut form.gender, Some(Gender::Female), "Female");
                     });
 
-                    ui.checkbox(&mut form.can_drive_minibus, "Can drive minibus");
+                    ui.separator();
+                    Self::credentials_fields(ui, &mut form.credentials);
+
+                    // Anything lapsed or about to lapse, as of the last save
+                    for warning in &form.warnings {
+                        ui.colored_label(egui::Color32::from_rgb(230, 140, 0), format!("⚠ {}", warning));
+                    }
 
                     ui.separator();
 
@@ -243,9 +362,18 @@ impl eframe::App for RowingApp {
 
                         ui.label("Car Seats:");
                         ui.text_edit_singleline(&mut form.car_seats);
+                        Self::haulage_fields(ui, &mut form.car_kit);
 
                         // "Wants to Drive" — only shown when the person has their own car
                         ui.checkbox(&mut form.wants_to_drive, "Wants to Drive");
+
+                        // Per-session override so a one-off change doesn't touch their standing preference
+                        ui.horizontal(|ui| {
+                            ui.label("This session:");
+                            ui.selectable_value(&mut form.session_drive, None,        "Default");
+                            ui.selectable_value(&mut form.session_drive, Some(true),  "Driving");
+                            ui.selectable_value(&mut form.session_drive, Some(false), "Not driving");
+                        });
                     });
 
                     ui.add_space(8.0);
@@ -281,6 +409,17 @@ impl eframe::App for RowingApp {
                     ui.label("Seats:");
                     ui.text_edit_singleline(&mut form.seats);
 
+                    ui.label("Depot:");
+                    egui::ComboBox::from_id_source("minibus_depot")
+                        .selected_text(form.depot.as_str())
+                        .show_ui(ui, |ui| {
+                            for point in &self.state.pickup_points {
+                                ui.selectable_value(&mut form.depot, point.name.clone(), &point.name);
+                            }
+                        });
+
+                    Self::haulage_fields(ui, &mut form.kit);
+
                     ui.add_space(8.0);
                     ui.horizontal(|ui| {
                         if ui.button("Save").clicked()   { commit_edit_minibus = true; }
@@ -298,6 +437,493 @@ impl eframe::App for RowingApp {
             self.edit_minibus_form = None;
         }
 
+        // --- EDIT VENUE POPUP WINDOW ---
+        let mut commit_edit_venue = false;
+        let mut cancel_edit_venue = false;
+        let mut delete_venue      = false;
+        if let Some(form) = &mut self.edit_venue_form {
+            let mut still_open = true;
+            egui::Window::new(if form.index.is_some() { "Edit Venue" } else { "Add Venue" })
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut still_open)
+                .show(ctx, |ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut form.name);
+
+                    ui.horizontal(|ui| {
+                        ui.label("Sheet Colour:");
+                        ui.color_edit_button_rgb(&mut form.colour);
+                    });
+
+                    ui.label("Address:");
+                    ui.text_edit_singleline(&mut form.address);
+
+                    ui.label("Latitude / Longitude:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut form.latitude).desired_width(80.0));
+                        ui.add(egui::TextEdit::singleline(&mut form.longitude).desired_width(80.0));
+                    });
+
+                    ui.label("Typical Travel Time (minutes):");
+                    ui.text_edit_singleline(&mut form.travel_minutes);
+
+                    ui.label("Typical Time at Venue (minutes):");
+                    ui.text_edit_singleline(&mut form.session_minutes);
+
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked()   { commit_edit_venue = true; }
+                        if form.index.is_some() && ui.button("Delete").clicked() { delete_venue = true; }
+                        if ui.button("Cancel").clicked() { cancel_edit_venue = true; }
+                    });
+                });
+
+            if !still_open { cancel_edit_venue = true; }
+        }
+
+        // Apply venue edits, deletion or cancel outside the borrow
+        if commit_edit_venue {
+            self.apply_edit_venue();
+        } else if delete_venue {
+            self.delete_edit_venue();
+        } else if cancel_edit_venue {
+            self.edit_venue_form = None;
+        }
+
+        // --- EDIT PICKUP POINT POPUP WINDOW ---
+        let mut commit_edit_pickup = false;
+        let mut cancel_edit_pickup = false;
+        let mut delete_pickup      = false;
+        if let Some(form) = &mut self.edit_pickup_form {
+            let mut still_open = true;
+            egui::Window::new(if form.index.is_some() { "Edit Pickup Point" } else { "Add Pickup Point" })
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut still_open)
+                .show(ctx, |ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut form.name);
+
+                    ui.label("Latitude / Longitude:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut form.latitude).desired_width(80.0));
+                        ui.add(egui::TextEdit::singleline(&mut form.longitude).desired_width(80.0));
+                    });
+
+                    ui.label("Notes:");
+                    ui.text_edit_multiline(&mut form.notes);
+
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked()   { commit_edit_pickup = true; }
+                        if form.index.is_some() && ui.button("Delete").clicked() { delete_pickup = true; }
+                        if ui.button("Cancel").clicked() { cancel_edit_pickup = true; }
+                    });
+                });
+
+            if !still_open { cancel_edit_pickup = true; }
+        }
+
+        // Apply pickup point edits, deletion or cancel outside the borrow
+        if commit_edit_pickup {
+            self.apply_edit_pickup();
+        } else if delete_pickup {
+            self.delete_edit_pickup();
+        } else if cancel_edit_pickup {
+            self.edit_pickup_form = None;
+        }
+
+        // --- SAVE SESSION AS POPUP WINDOW ---
+        let mut commit_save_as = false;
+        let mut cancel_save_as = false;
+        if let Some(name) = &mut self.save_as_name {
+            let mut still_open = true;
+            egui::Window::new("Save Session As")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut still_open)
+                .show(ctx, |ui| {
+                    ui.label("Session Name:");
+                    ui.text_edit_singleline(name);
+
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked()   { commit_save_as = true; }
+                        if ui.button("Cancel").clicked() { cancel_save_as = true; }
+                    });
+                });
+
+            if !still_open { cancel_save_as = true; }
+        }
+
+        // Renames the session and saves it outside the borrow
+        if commit_save_as {
+            self.apply_save_as();
+        } else if cancel_save_as {
+            self.save_as_name = None;
+        }
+
+        // --- SESSION CONSTRAINTS POPUP WINDOW ---
+        let mut add_constraint    = false;
+        let mut remove_constraint = None;
+        let mut close_constraints = false;
+        if let Some(form) = &mut self.constraint_form {
+            let mut still_open = true;
+            let people         = &self.state.people;
+            let name_of        = |id: &str| people.iter().find(|p| p.student_id == id).map(|p| p.name.clone()).unwrap_or(id.to_string());
+            egui::Window::new("Session Constraints")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut still_open)
+                .show(ctx, |ui| {
+
+                    // Existing constraints, each with a remove button
+                    if self.state.session.constraints.is_empty() {
+                        ui.label("No constraints for this session.");
+                    }
+                    for (c_idx, constraint) in self.state.session.constraints.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let names: Vec<String> = constraint.people().iter().map(|id| name_of(id)).collect();
+                            ui.label(format!("{}: {}", constraint.kind().label(), names.join(", ")));
+                            if ui.small_button("Remove").clicked() { remove_constraint = Some(c_idx); }
+                        });
+                    }
+
+                    ui.separator();
+                    ui.label("Add:");
+                    egui::ComboBox::from_id_source("constraint_kind")
+                        .selected_text(form.kind.label())
+                        .show_ui(ui, |ui| {
+                            for kind in ConstraintKind::ALL {
+                                ui.selectable_value(&mut form.kind, kind, kind.label());
+                            }
+                        });
+
+                    // Anyone seated on the lake can be picked
+                    let mut seated: Vec<&Person> = people.iter().filter(|p| assigned_ids.contains(&p.student_id)).collect();
+                    seated.sort_by(|a, b| a.name.cmp(&b.name));
+                    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                        for p in seated {
+                            let mut ticked = form.people.contains(&p.student_id);
+                            if ui.checkbox(&mut ticked, &p.name).changed() {
+                                if ticked {
+                                    form.people.push(p.student_id.clone());
+                                } else {
+                                    form.people.retain(|id| id != &p.student_id);
+                                }
+                            }
+                        }
+                    });
+
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Add").clicked()   { add_constraint    = true; }
+                        if ui.button("Close").clicked() { close_constraints = true; }
+                    });
+                });
+
+            if !still_open { close_constraints = true; }
+        }
+
+        // Updating the session's constraints outside the borrow
+        if let Some(c_idx) = remove_constraint {
+            self.state.session.constraints.remove(c_idx);
+        }
+        if add_constraint && let Some(form) = &mut self.constraint_form {
+            match form.kind.build(form.people.clone()) {
+                Ok(constraint) => {
+                    self.state.session.constraints.push(constraint);
+                    form.people.clear();
+                }
+                Err(msg) => self.error_message = Some(msg),
+            }
+        }
+        if close_constraints {
+            self.constraint_form = None;
+        }
+
+        // --- SESSION EQUIPMENT POPUP WINDOW ---
+        let mut add_equipment    = false;
+        let mut remove_equipment = None;
+        let mut close_equipment  = false;
+        if let Some(form) = &mut self.equipment_form {
+            let mut still_open = true;
+            let venues         = &self.state.venues;
+            egui::Window::new("Session Equipment")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut still_open)
+                .show(ctx, |ui| {
+
+                    // Equipment already needed, each with a remove button
+                    if self.state.session.equipment.is_empty() {
+                        ui.label("No equipment for this session.");
+                    }
+                    for (e_idx, need) in self.state.session.equipment.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{} {}: {}", need.departure_time, venue_label(venues, &need.destination), need.kind.describe(need.quantity as usize)));
+                            if ui.small_button("Remove").clicked() { remove_equipment = Some(e_idx); }
+                        });
+                    }
+
+                    ui.separator();
+                    ui.label("Add:");
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_source("equipment_kind")
+                            .selected_text(form.kind.label())
+                            .show_ui(ui, |ui| {
+                                for kind in EquipmentKind::ALL {
+                                    ui.selectable_value(&mut form.kind, kind, kind.label());
+                                }
+                            });
+                        ui.add(egui::TextEdit::singleline(&mut form.quantity).desired_width(30.0).hint_text("1"))
+                            .on_hover_text("How many (sets of blades, ergs, cans of fuel)");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut form.departure_time).desired_width(45.0).hint_text("07:00"))
+                            .on_hover_text("Departure Time (e.g. 07:00)");
+                        egui::ComboBox::from_id_source("equipment_destination")
+                            .selected_text(form.destination.as_ref().map(|d| venue_label(venues, d)).unwrap_or("Location"))
+                            .show_ui(ui, |ui| {
+                                for venue in venues {
+                                    ui.selectable_value(&mut form.destination, Some(venue.id.clone()), &venue.name);
+                                }
+                            });
+                    });
+
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Add").clicked()   { add_equipment   = true; }
+                        if ui.button("Close").clicked() { close_equipment = true; }
+                    });
+                });
+
+            if !still_open { close_equipment = true; }
+        }
+
+        // Updating the session's equipment outside the borrow
+        if let Some(e_idx) = remove_equipment {
+            self.state.session.equipment.remove(e_idx);
+        }
+        if add_equipment && let Some(form) = &self.equipment_form {
+            match form.build() {
+                Ok(need) => self.state.session.equipment.push(need),
+                Err(msg) => self.error_message = Some(msg),
+            }
+        }
+        if close_equipment {
+            self.equipment_form = None;
+        }
+
+        // --- DRIVING STATS POPUP: DRIVES PER PERSON FOR EACH TERM, FROM THE PUBLISHED SHEETS ---
+        if self.show_driving_stats {
+            let mut still_open = true;
+            egui::Window::new("Driving Stats")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut still_open)
+                .show(ctx, |ui| {
+                    let terms = self.state.history.term_stats();
+                    if terms.is_empty() {
+                        ui.label("Nobody has driven yet - drives are recorded each time a sheet is published.");
+                    }
+
+                    // Newest term open, older ones folded away
+                    for (t_idx, (term, drivers)) in terms.iter().enumerate() {
+                        egui::CollapsingHeader::new(term).default_open(t_idx == 0).show(ui, |ui| {
+                            egui::Grid::new(("driving_stats", t_idx)).striped(true).show(ui, |ui| {
+                                for heading in ["Name", "Minibus", "Car", "Total", "Last drove"] {
+                                    ui.label(egui::RichText::new(heading).strong());
+                                }
+                                ui.end_row();
+
+                                for d in drivers {
+                                    ui.label(&d.name);
+                                    ui.label(d.minibus.to_string());
+                                    ui.label(d.car.to_string());
+                                    ui.label(d.total().to_string());
+                                    ui.label(&d.last_drove);
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    }
+                });
+
+            if !still_open { self.show_driving_stats = false; }
+        }
+
+        // --- EXPENSES POPUP: MILEAGE CLAIMS FOR A DATE RANGE, PREVIEWED AND EXPORTED AS CSV OR PDF ---
+        let mut export_expenses: Option<bool> = None;          // Some(true) for PDF, Some(false) for CSV
+        let mut close_expenses = false;
+        if let Some(form) = &mut self.expense_form {
+            let mut still_open = true;
+            let report = form.build(&self.state);
+            egui::Window::new("Expenses")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut still_open)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("From:");
+                        ui.add(egui::TextEdit::singleline(&mut form.from).desired_width(80.0).hint_text("YYYY-MM-DD"));
+                        ui.label("To:");
+                        ui.add(egui::TextEdit::singleline(&mut form.to).desired_width(80.0).hint_text("YYYY-MM-DD"));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("£ per mile:");
+                        ui.add(egui::TextEdit::singleline(&mut form.per_mile).desired_width(40.0));
+                        ui.label("£ per passenger mile:");
+                        ui.add(egui::TextEdit::singleline(&mut form.per_passenger_mile).desired_width(40.0));
+                    });
+                    ui.separator();
+
+                    // Preview of what each driver is owed, as it'll be exported
+                    match &report {
+                        Err(msg) => { ui.colored_label(egui::Color32::RED, msg); }
+                        Ok(report) if report.claims.is_empty() => { ui.label("No car journeys were published in this period."); }
+                        Ok(report) => {
+                            egui::Grid::new("expense_preview").striped(true).show(ui, |ui| {
+                                for heading in ["Driver", "Trips", "Miles", "Claim"] {
+                                    ui.label(egui::RichText::new(heading).strong());
+                                }
+                                ui.end_row();
+                                for claim in &report.claims {
+                                    ui.label(&claim.name);
+                                    ui.label(claim.trips.len().to_string());
+                                    ui.label(format!("{:.1}", claim.miles()));
+                                    ui.label(format!("£{}", pounds(claim.total(&report.rates))));
+                                    ui.end_row();
+                                }
+                            });
+                            ui.label(egui::RichText::new(format!("Total to pay: £{}", pounds(report.total()))).strong());
+                        }
+                    }
+
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        ui.add_enabled_ui(report.is_ok(), |ui| {
+                            if ui.button("Export CSV").clicked() { export_expenses = Some(false); }
+                            if ui.button("Export PDF").clicked() { export_expenses = Some(true); }
+                        });
+                        if ui.button("Close").clicked() { close_expenses = true; }
+                    });
+                });
+
+            if !still_open { close_expenses = true; }
+        }
+
+        // Writing the claims outside the borrow, named after the date range
+        if let Some(pdf) = export_expenses && let Some(form) = &self.expense_form && let Ok(report) = form.build(&self.state) {
+            let stem    = format!("mileage_{}_to_{}", report.from, report.to);
+            let written = if pdf {
+                crate::pdf::generate_expense_pdf(&report, &self.state.theme, &format!("{}.pdf", stem)).map(|_| format!("{}.pdf", stem))
+            } else {
+                std::fs::write(format!("{}.csv", stem), report.to_csv()).map(|_| format!("{}.csv", stem)).map_err(|e| e.into())
+            };
+            self.error_message = Some(match written {
+                Ok(file) => format!("Wrote {} ({} driver(s), £{} in total).", file, report.claims.len(), pounds(report.total())),
+                Err(e)   => format!("Could not write the mileage claims: {}", e),
+            });
+        }
+        if close_expenses {
+            self.expense_form = None;
+        }
+
+        // --- SUPPORT STAFF TRAY (LAKE VIEW ONLY) ---
+        // Coaches, launch drivers, spares and cox trainees aren't in a boat but still travel with a departure
+        let mut add_support    = false;
+        let mut remove_support = None;
+        if !self.show_transport {
+            let people = &self.state.people;
+            let venues = &self.state.venues;
+            let can_add = self.selected_id.as_ref().is_some_and(|id| !assigned_ids.contains(id));
+            egui::Window::new("Support Staff")
+                .default_pos(egui::pos2(260.0, 600.0))
+                .resizable(false)
+                .show(ctx, |ui| {
+                    if ui.add_enabled(can_add, egui::Button::new("Add selected"))
+                        .on_hover_text("Select someone in the sidebar who isn't in a boat, then add them here")
+                        .clicked() {
+                        add_support = true;
+                    }
+                    if self.state.session.support.is_empty() {
+                        ui.label("Nobody in the tray.");
+                    }
+
+                    for (s_idx, staff) in self.state.session.support.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            let name = people.iter().find(|p| p.student_id == staff.student_id).map(|p| p.name.as_str()).unwrap_or(&staff.student_id);
+                            ui.label(name);
+
+                            egui::ComboBox::from_id_source(("support_role", s_idx))
+                                .selected_text(staff.role.label())
+                                .show_ui(ui, |ui| {
+                                    for role in SupportRole::ALL {
+                                        ui.selectable_value(&mut staff.role, role, role.label());
+                                    }
+                                });
+                            ui.add(egui::TextEdit::singleline(&mut staff.departure_time).desired_width(45.0))
+                                .on_hover_text("Departure Time (e.g. 07:00)");
+                            egui::ComboBox::from_id_source(("support_destination", s_idx))
+                                .selected_text(staff.destination.as_ref().map(|d| venue_label(venues, d)).unwrap_or("Location"))
+                                .show_ui(ui, |ui| {
+                                    for venue in venues {
+                                        ui.selectable_value(&mut staff.destination, Some(venue.id.clone()), &venue.name);
+                                    }
+                                });
+                            ui.checkbox(&mut staff.prefers_driving, "Drives")
+                                .on_hover_text("Put them forward as a driver before the rowers");
+                            if ui.small_button("Remove").clicked() { remove_support = Some(s_idx); }
+                        });
+                    }
+                });
+        }
+
+        // Updating the tray outside the borrow
+        if add_support && let Some(id) = self.selected_id.take() {
+            self.state.session.support.push(SupportStaff::new(id));
+        }
+        if let Some(s_idx) = remove_support {
+            self.state.session.support.remove(s_idx);
+        }
+
+        // --- OPEN SESSION POPUP WINDOW ---
+        if self.show_open_session {
+            let mut still_open = true;
+            let mut open_file: Option<String> = None;
+
+            egui::Window::new("Open Session")
+                .collapsible(false)
+                .resizable(false)
+                .open(&mut still_open)
+                .show(ctx, |ui| {
+                    let files = SystemState::list_sessions();
+                    if files.is_empty() {
+                        ui.label(format!("No saved sessions in '{}'.", SystemState::SESSIONS_DIR));
+                    }
+
+                    // One button per saved session file
+                    for file in files {
+                        if ui.button(&file).clicked() {
+                            open_file = Some(file);
+                        }
+                    }
+                });
+
+            if let Some(file) = open_file {
+                if let Err(e) = self.state.load_session(&file) {
+                    self.error_message = Some(format!("Could not open session: {}", e));
+                }
+                self.selected_id       = None;
+                self.transport_plan    = None;          // The plan belonged to the previous session's crews
+                self.show_open_session = false;
+            } else if !still_open {
+                self.show_open_session = false;
+            }
+        }
+
         // --- SQUAD ---
         egui::SidePanel::left("Rowing Transport").show(ctx, |ui| {
             ui.heading("Transport Automation");                                 // Heading for the sidebar
@@ -323,30 +949,35 @@ impl eframe::App for RowingApp {
                         let is_selected = self.selected_id.as_ref() == Some(&p.student_id);
 
                         // Updates people in sidebar to indicate if they're assigned to a boat
-                        let mut label_text = "".to_string();
-                        if is_assigned {
-                            label_text = format!("✔ {}", p.name);                                              // Displays a checkmark if the person is already assigned to a boat
+                        let label_text = if is_assigned {
+                            let text = format!("✔ {}", p.name);                                              // Displays a checkmark if the person is already assigned to a boat
 
                             if is_selected {
                                 ui.visuals_mut().override_text_color = Some(egui::Color32::from_rgb(50, 150, 50));  // Highlights selected person's name in green
                             }
+                            text
                         } else {
-                            label_text = p.name.clone();                                                            // Simply displays the persons name as intended
-                        }
+                            p.name.clone()                                                                          // Simply displays the persons name as intended
+                        };
+
+                        // Flags anyone whose licence, assessment or insurance has lapsed or is about to
+                        let warnings = p.driving_warnings(self.state.session.outing_date());
+                        let label_text = if warnings.is_empty() { label_text } else { format!("{} ⚠", label_text) };
 
                         // Handles label selection and deselection logic when clicking a persons name
-                        let label_resp = ui.selectable_label(is_selected, label_text);  // Creates a selectable label for each person
+                        let mut label_resp = ui.selectable_label(is_selected, label_text);  // Creates a selectable label for each person
+                        if !warnings.is_empty() {
+                            label_resp = label_resp.on_hover_text(warnings.join("\n"));
+                        }
 
                         if label_resp.clicked() {
                             if is_selected && is_assigned {                         // logic to search through every boat and remove the selected person from their seat
-                                for boat in &mut self.boats {
-                                    for seat in &mut boat.seats {
-                                        if seat.as_ref() == Some(&p.student_id) {
-                                            *seat = None;                           // Setting the seat to none (removes the person from that boat)
-                                            break;
-                                        }
+                                for boat in &mut self.state.session.boats {
+                                    if let Some(s_idx) = boat.seats.iter().position(|s| s.as_ref() == Some(&p.student_id)) {
+                                        boat.set_seat(s_idx, None);             // Emptying the seat (removes the person from that boat)
                                     }
                                 }
+                                self.state.session.support.retain(|s| s.student_id != p.student_id);   // Or takes them out of the support staff tray
                                 self.selected_id = None;                            // Then simply deselects the persons ID after they've been removed
                             } else if is_selected {                                 // If the person is selected but not assigned then it deselects them
                                 self.selected_id = None;
@@ -428,6 +1059,85 @@ impl eframe::App for RowingApp {
                         index: mb_idx,
                         registration: mb.registration.clone(),
                         seats: mb.seats.to_string(),
+                        depot: mb.depot.clone(),
+                        kit: HaulageForm::from(&mb.kit),
+                    });
+                }
+
+                ui.add_space(12.0);
+                ui.separator();
+
+                // --- PICKUP POINTS SECTION ---
+                ui.heading("Pickup Points");
+                ui.add_space(4.0);
+
+                // Collect pickup point edit requests to avoid borrow issues
+                let mut open_pickup_edit_for: Option<Option<usize>> = None;
+
+                for (p_idx, point) in self.state.pickup_points.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(&point.name).on_hover_text(&point.notes);
+                        if ui.small_button("Edit").clicked() {
+                            open_pickup_edit_for = Some(Some(p_idx));
+                        }
+                    });
+                }
+
+                if ui.button("＋ Add Pickup Point").clicked() {
+                    open_pickup_edit_for = Some(None);
+                }
+
+                // Opens the pickup point window either populated from an existing point or blank for a new one
+                if let Some(p_idx) = open_pickup_edit_for {
+                    let existing = p_idx.map(|i| &self.state.pickup_points[i]);
+                    self.edit_pickup_form = Some(EditPickupForm {
+                        index:     p_idx,
+                        name:      existing.map(|p| p.name.clone()).unwrap_or_default(),
+                        latitude:  existing.map(|p| p.latitude.to_string()).unwrap_or_default(),
+                        longitude: existing.map(|p| p.longitude.to_string()).unwrap_or_default(),
+                        notes:     existing.map(|p| p.notes.clone()).unwrap_or_default(),
+                    });
+                }
+
+                ui.add_space(12.0);
+                ui.separator();
+
+                // --- VENUES SECTION ---
+                ui.heading("Venues");
+                ui.add_space(4.0);
+
+                // Collect venue edit requests to avoid borrow issues
+                let mut open_venue_edit_for: Option<Option<usize>> = None;
+
+                for (v_idx, venue) in self.state.venues.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let [r, g, b] = venue.colour.map(|c| (c * 255.0) as u8);
+                        ui.colored_label(egui::Color32::from_rgb(r, g, b), "■");        // Swatch of the venue's transport sheet colour
+                        ui.label(format!("{} — {} min", venue.name, venue.travel_minutes));
+                        if ui.small_button("Edit").clicked() {
+                            open_venue_edit_for = Some(Some(v_idx));
+                        }
+                    });
+                }
+
+                if ui.button("＋ Add Venue").clicked() {
+                    open_venue_edit_for = Some(None);
+                }
+
+                ui.add_space(8.0);
+
+                // Opens the venue window either populated from an existing venue or blank for a new one
+                if let Some(v_idx) = open_venue_edit_for {
+                    let existing = v_idx.map(|i| &self.state.venues[i]);
+                    self.edit_venue_form = Some(EditVenueForm {
+                        index:           v_idx,
+                        name:            existing.map(|v| v.name.clone()).unwrap_or_default(),
+                        colour:          existing.map(|v| v.colour).unwrap_or([1.0, 1.0, 1.0]),
+                        address:         existing.map(|v| v.address.clone()).unwrap_or_default(),
+                        latitude:        existing.map(|v| v.latitude.to_string()).unwrap_or_default(),
+                        longitude:       existing.map(|v| v.longitude.to_string()).unwrap_or_default(),
+                        travel_minutes:  existing.map(|v| v.travel_minutes.to_string()).unwrap_or_default(),
+                        session_minutes: existing.map(|v| v.session_minutes).unwrap_or(Venue::default_session_minutes()).to_string(),
                     });
                 }
             });
@@ -442,85 +1152,164 @@ impl eframe::App for RowingApp {
                     gender: Some(p.gender.clone()),
                     student_id: p.student_id.clone(),
                     year_of_entry: p.year_of_entry.to_string(),
-                    pickup_locations: p.pickup_locations.join(", "),
-                    can_drive_minibus: p.can_drive_minibus,
+                    pickup_locations: p.pickup_locations.clone(),
+                    phone:            p.phone.clone(),
+                    credentials:      p.credentials.clone(),
                     has_car,
-                    wants_to_drive:   self.wants_to_drive.contains(&p.student_id),  // Pre-populate from current state
+                    wants_to_drive:   p.wants_to_drive,
+                    session_drive:    self.state.session.driver_overrides.get(&p.student_id).copied(),
                     car_type:         p.car.as_ref().map(|c| c.vehicle_type.clone()).unwrap_or_default(),
                     car_registration: p.car.as_ref().map(|c| c.registration.clone()).unwrap_or_default(),
                     car_seats:        p.car.as_ref().map(|c| c.seats.to_string()).unwrap_or_default(),
+                    car_kit:          p.car.as_ref().map(|c| HaulageForm::from(&c.kit)).unwrap_or_default(),
+                    warnings:         p.driving_warnings(self.state.session.outing_date()),
                 });
             }
 
             // Handle deletion outside the scroll area borrow — removes the person, clears their seat
-            // assignments from any boats, removes their wants_to_drive entry, and saves
+            // assignments from any boats, removes their session driving override, and saves
             if let Some(idx) = delete_person_idx {
                 let removed_id = self.state.people[idx].student_id.clone();
 
                 self.state.people.remove(idx);                              // Remove the person from the squad
 
-                for boat in &mut self.boats {                               // Clear any seat they were assigned to on the lake
-                    for seat in &mut boat.seats {
-                        if seat.as_ref() == Some(&removed_id) {
-                            *seat = None;
-                        }
+                for boat in &mut self.state.session.boats {                 // Clear any seat they were assigned to on the lake
+                    while let Some(s_idx) = boat.seats.iter().position(|s| s.as_ref() == Some(&removed_id)) {
+                        boat.set_seat(s_idx, None);
                     }
                 }
 
-                self.wants_to_drive.remove(&removed_id);                   // Remove their wants-to-drive preference
+                self.state.session.driver_overrides.remove(&removed_id);   // Remove their wants-to-drive override for this session
 
                 if self.selected_id.as_ref() == Some(&removed_id) {        // Deselect them if they were selected
                     self.selected_id = None;
                 }
 
-                self.state.save_all().ok();                                 // Persist the change to JSON
+                self.save_squad();                                     // Persist the change to JSON
             }
         });
 
         // --- MAIN FRAME ---
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {                        // Top horizontal bar for adding boats and publishing
-                ui.label("Add Boat:");
-                let types = [
-                    BoatType::Single, 
-                    BoatType::Double, 
-                    BoatType::Pair,
-                    BoatType::Quad, 
-                    BoatType::Four, 
-                    BoatType::FourCoxed, 
-                    BoatType::EightCoxed
-                ];
+                ui.selectable_value(&mut self.show_transport, false, "Lake");              // Switching between the crews and the vehicles they were given
+                ui.selectable_value(&mut self.show_transport, true, "Transport");
+                ui.separator();
 
-                // logic for each boat to instantiate a boat of that type and add it to the frame
-                for t in types {
-                    if ui.button(t.label()).clicked() { self.add_boat(t); }
+                if !self.show_transport {
+                    ui.label("Add Boat:");
+                    let types = [
+                        BoatType::Single, 
+                        BoatType::Double, 
+                        BoatType::Pair,
+                        BoatType::Quad, 
+                        BoatType::Four, 
+                        BoatType::FourCoxed, 
+                        BoatType::EightCoxed
+                    ];
+
+                    // logic for each boat to instantiate a boat of that type and add it to the frame
+                    for t in types {
+                        if ui.button(t.label()).clicked() { self.add_boat(t); }
+                    }
+                    ui.separator();                                                         // A seperator line to make it look cleaner
                 }
-                
-                ui.separator();                                                             // A seperator line to make it look cleaner
-                if ui.button("Publish & PDF").clicked() { self.validate_and_publish(); }    // Button to trigger the validation and PDF generation process
-                if ui.button("Clear Lake")   .clicked() { self.boats.clear(); }             // Button to clear all boats from the frame
+
+                egui::ComboBox::from_id_source("strategy")                                  // Dropdown for the allocation strategy used when publishing
+                    .selected_text(self.strategy.label())
+                    .show_ui(ui, |ui| {
+                        for kind in StrategyKind::ALL {
+                            ui.selectable_value(&mut self.strategy, kind, kind.label());
+                        }
+                    });
+                ui.checkbox(&mut self.publish_partial, "Allow partial")                     // Still publish when someone can't be seated
+                    .on_hover_text("Publish the best partial plan with a \"Needs transport\" box for anyone left over");
+                ui.checkbox(&mut self.fair_rota, "Fair rota")                               // Share the driving out using the published history
+                    .on_hover_text("Hand each vehicle to whoever on board has driven least recently");
+
+                if ui.button("Constraints").on_hover_text("Who rides together or apart, who must or mustn't drive, and who travels privately").clicked() {
+                    self.constraint_form = Some(ConstraintForm::default());
+                }
+                if ui.button("Equipment").on_hover_text("Trailers, blades, ergs and fuel to take on each departure").clicked() {
+                    self.equipment_form = Some(EquipmentForm::default());
+                }
+                if ui.button("Driving Stats").on_hover_text("Drives per person each term, from the published sheets").clicked() {
+                    self.show_driving_stats = true;
+                }
+                if ui.button("Expenses").on_hover_text("Mileage claims for car journeys on the published sheets").clicked() {
+                    self.expense_form = Some(ExpenseForm::default());
+                }
+
+                if self.show_transport {
+                    if ui.button("Re-run").on_hover_text("Allocate again from the crews, keeping locked choices").clicked() {
+                        self.validate_and_allocate();
+                    }
+                    if ui.button("PDF from Plan").clicked() { self.publish(); }            // Prints the plan as edited
+                    if ui.button("Driver Slips").on_hover_text("A page for each driver: who to collect, where, when and their numbers").clicked() {
+                        self.publish_handouts(true);
+                    }
+                    if ui.button("Itineraries").on_hover_text("Everyone's own journeys there and back").clicked() {
+                        self.publish_handouts(false);
+                    }
+
+                    egui::ComboBox::from_id_source("export_format")                         // Dropdown for the spreadsheet / chat / calendar export
+                        .selected_text(self.export_format.label())
+                        .show_ui(ui, |ui| {
+                            for format in ExportFormat::ALL {
+                                ui.selectable_value(&mut self.export_format, format, format.label());
+                            }
+                        });
+                    if ui.button("Export").on_hover_text("Write the vehicles in the chosen format (Markdown is copied for pasting too)").clicked() {
+                        self.export_plan(ui);
+                    }
+                } else {
+                    if ui.button("Publish & PDF").clicked() { self.validate_and_publish(); }    // Button to trigger the validation and PDF generation process
+                    if ui.button("Clear Lake")   .clicked() { self.state.session.boats.clear(); }   // Button to clear all boats from the frame
+                }
+
+                // Session buttons to keep crew plans between runs of the app
+                ui.separator();
+                if ui.button("Open").clicked() { self.show_open_session = true; }
+                if ui.button("Save").clicked() { self.save_session(); }
+                if ui.button("Save As").clicked() { self.save_as_name = Some(self.state.session.name.clone()); }
+
+                if !self.state.session.name.is_empty() {
+                    ui.label(format!("Session: {}", self.state.session.name));
+                }
+                ui.label("Outing date:");
+                ui.add(egui::TextEdit::singleline(&mut self.state.session.date).desired_width(80.0).hint_text("YYYY-MM-DD"))
+                    .on_hover_text("Day the crews go out - drivers' licences and insurance are checked against it (blank for today)");
             });
             
-            for (b_idx, boat) in self.boats.iter_mut().enumerate() {
+            if self.show_transport {
+                self.transport_view(ui);
+                return;
+            }
+
+            let people = &self.state.people;                                        // Borrowed separately from the session so seats can be edited while drawing
+            let venues = &self.state.venues;
+            let pickup_points = &self.state.pickup_points;
+            for (b_idx, boat) in self.state.session.boats.iter_mut().enumerate() {
 
                 // Calculating the dimensions for the boat based on the number of seats
+                let pos           = egui::pos2(boat.pos.0, boat.pos.1);
                 let num_seats     = boat.seats.len();
                 let seat_spacing  = 40.0;
                 let boat_width    = 30.0; 
-                let header_height = 60.0; 
+                let header_height = 110.0; 
                 let total_height  = (num_seats as f32 * seat_spacing) + 140.0 + header_height; 
                 
                 // Calculating edge points for the boat based on the position and calculated height
-                let top    = boat.pos.y - (total_height / 2.0);
-                let bottom = boat.pos.y + (total_height / 2.0);
-                let left   = boat.pos.x - (boat_width   / 2.0);
-                let right  = boat.pos.x + (boat_width   / 2.0);
+                let top    = pos.y - (total_height / 2.0);
+                let bottom = pos.y + (total_height / 2.0);
+                let left   = pos.x - (boat_width   / 2.0);
+                let right  = pos.x + (boat_width   / 2.0);
 
                 // --- INPUTS ABOVE THE BOAT ---
-                // handling the main box to hold both the input lines for departure time and destination selection
+                // handling the main box to hold the input lines for departure time, destination, return time and drop-off point
                 let input_rect = egui::Rect::from_min_size(
                     egui::pos2(left - 40.0, top),           // Positioning the box
-                    egui::vec2(110.0, 60.0)                 // Size of the input box
+                    egui::vec2(110.0, 110.0)                // Size of the input box
                 );
                 
                 // configuring what the box contains (input lines)
@@ -530,10 +1319,22 @@ impl eframe::App for RowingApp {
                         ui.text_edit_singleline(&mut boat.departure_time).on_hover_text("Departure Time (e.g. 07:00)");         // Input for departure time
                         
                         egui::ComboBox::from_id_source(b_idx)
-                            .selected_text(boat.destination.map(|d| d.label()).unwrap_or("Location"))                           // Dropdown for destination selection
+                            .selected_text(boat.destination.as_ref().map(|d| venue_label(venues, d)).unwrap_or("Location"))    // Dropdown for destination selection
+                            .show_ui(ui, |ui| {
+                                for venue in venues {
+                                    ui.selectable_value(&mut boat.destination, Some(venue.id.clone()), &venue.name);
+                                }
+                            });
+
+                        ui.text_edit_singleline(&mut boat.return_time).on_hover_text("Return Time (e.g. 09:30) - blank uses the venue's usual session length");
+
+                        egui::ComboBox::from_id_source(("drop_off", b_idx))
+                            .selected_text(boat.drop_off.as_deref().unwrap_or("Drop-off"))                                      // Dropdown for where the crew is dropped on the way back
                             .show_ui(ui, |ui| {
-                                ui.selectable_value(&mut boat.destination, Some(Destination::StrathclydePark), "Strathclyde");
-                                ui.selectable_value(&mut boat.destination, Some(Destination::Auchenstarry),    "Auchenstarry");
+                                ui.selectable_value(&mut boat.drop_off, None, "Where they got in");
+                                for point in pickup_points {
+                                    ui.selectable_value(&mut boat.drop_off, Some(point.name.clone()), &point.name);
+                                }
                             });
                     })
                     .response   // Adding a response to the entire input area to allow dragging the boat by clicking and dragging on the inputs as well
@@ -544,10 +1345,10 @@ impl eframe::App for RowingApp {
 
                 let shell_start_y = top + header_height;        // Starting Y position for the shell (where the first seat is - below the input area)
                 let shell_points  = vec![                       // Defining the points for the polygon to draw the boat shell
-                    egui::pos2(boat.pos.x, shell_start_y), 
+                    egui::pos2(pos.x, shell_start_y), 
                     egui::pos2(right, shell_start_y + 75.0),
                     egui::pos2(right, bottom - 75.0), 
-                    egui::pos2(boat.pos.x, bottom),
+                    egui::pos2(pos.x, bottom),
                     egui::pos2(left, bottom - 75.0), 
                     egui::pos2(left, shell_start_y + 75.0),
                 ];
@@ -559,45 +1360,39 @@ impl eframe::App for RowingApp {
                 ));
 
                 // --- DRAWING SEATS ---
-                for (s_idx, seat) in boat.seats.iter_mut().enumerate() {
-                    let seat_pos = egui::pos2(boat.pos.x, (shell_start_y + 85.0) + (s_idx as f32 * seat_spacing));                              // Positioning each seat with some spacing below the boat shell
-                    let is_cox = (boat.boat_type == BoatType::FourCoxed || boat.boat_type == BoatType::EightCoxed) && s_idx == (num_seats - 1); // Identifying if the current seat is the coxswain seat (last seat in coxed boats)
+                let (mut seat_clicked, mut toggle_own_transport) = (None, None);
+                for (s_idx, seat) in boat.seats.iter().enumerate() {
+                    let own_transport = seat.as_ref().is_some_and(|id| boat.own_transport.contains(id));
+                    let seat_pos = egui::pos2(pos.x, (shell_start_y + 85.0) + (s_idx as f32 * seat_spacing));                              // Positioning each seat with some spacing below the boat shell
+                    let is_cox = boat.boat_type.has_cox() && s_idx == (num_seats - 1); // Identifying if the current seat is the coxswain seat (last seat in coxed boats)
 
                     let seat_hitbox = egui::Rect::from_center_size(seat_pos, egui::vec2(30.0, 30.0));   // Creating a hitbox around each seat (for clicking and assigning passengers)
                     
-                    // Handling the logic for when a seat is clicked to assign or unassign a person
-                    if ui.rect_contains_pointer(seat_hitbox) && ui.input(|i| i.pointer.any_click()) {
-                        if let Some(id) = &self.selected_id {
-                            *seat            = Some(id.clone());
-                            self.selected_id = None;
-                        }
+                    // Handling the logic for when a seat is clicked to assign a person (left click only, right click is below)
+                    if ui.rect_contains_pointer(seat_hitbox) && ui.input(|i| i.pointer.primary_clicked()) && self.selected_id.is_some() {
+                        seat_clicked = Some(s_idx);
+                    }
+
+                    // Right clicking a filled seat marks whoever sits there as making their own way (and back again)
+                    if ui.rect_contains_pointer(seat_hitbox) && ui.input(|i| i.pointer.secondary_clicked()) && seat.is_some() {
+                        toggle_own_transport = Some(s_idx);
                     }
 
                     // Defining colours for seats based on their status
-                    let color = if seat.is_some() { egui::Color32::from_rgb(50, 200, 50) }  // Seats that are filled with a person are colored green 
+                    let color = if own_transport { egui::Color32::from_rgb(70, 130, 230) }  // People making their own way are colored blue
+                                else if seat.is_some() { egui::Color32::from_rgb(50, 200, 50) }  // Seats that are filled with a person are colored green 
                                 else if is_cox { egui::Color32::from_rgb(180, 60, 60) }     // Coxswain seats that are empty are colored red
                                 else { egui::Color32::WHITE };                              // Regular empty seats are colored white
                     
                     painter.circle_filled(seat_pos, 10.0, color);   // Drawing the seat as a filled circle with the determined color
 
                     // Determining the name of each seat to be displayed on the boat
-                    let name  = seat.as_ref().and_then(|id| self.state.get_person(id)).map(|p| p.name.as_str()).unwrap_or("—"); // Getting the name of the assigned person for that seat (or "-" if the seat is empty)
-                    let has_cox: bool = (boat.boat_type == BoatType::FourCoxed || boat.boat_type == BoatType::EightCoxed);
-
-                    let mut label; // Initializing the label (name to be given to the seat)
-                    if is_cox {
-                        label = format!("COX: {}", name);           // Label for coxswain seat
-                    } else if s_idx == 0 {
-                        label = format!("BOW: {}", name);           // Label for bow seat
-                    } else if (has_cox && s_idx == num_seats - 2) {
-                        label = format!("STR: {}", name);           // Label for stroke seat - condition that boat is coxed
-                    } else {
-                        if(s_idx == num_seats - 1) {
-                            label = format!("STR: {}", name);       // Label for stroke seat - condition that boat is coxless
-                        } else {
-                        label = format!("{}: {}", s_idx + 1, name); // Label for regular seats (e.g. "2: Alice")
-                        }
-                    }
+                    let name  = seat.as_ref().and_then(|id| people.iter().find(|p| &p.student_id == id)).map(|p| p.name.as_str()).unwrap_or("—"); // Getting the name of the assigned person for that seat (or "-" if the seat is empty)
+
+                    // Initializing the label (BOW / STR / COX, or the seat number for regular seats, e.g. "2: Alice")
+                    let label = format!("{}: {}", boat.boat_type.seat_label(s_idx), name);
+
+                    let label = if own_transport { format!("{} (own way)", label) } else { label };
 
                     // Drawing the label for each seat (Showing each seat number)
                     painter.text(seat_pos + egui::vec2(28.0, 0.0), 
@@ -607,10 +1402,17 @@ impl eframe::App for RowingApp {
                                  egui::Color32::WHITE);
                 }
 
+                if let Some(s_idx) = seat_clicked {
+                    boat.set_seat(s_idx, self.selected_id.take());
+                }
+                if let Some(s_idx) = toggle_own_transport {
+                    boat.toggle_own_transport(s_idx);
+                }
+
                 // --- DRAGGING BOATS LOGIC ---
                 // Positioning the draggable area for the boat (the entire boat including the input area)
-                let boat_rect = egui::Rect::from_center_size(egui::pos2(boat.pos.x,
-                                                                        boat.pos.y + (header_height/2.0)),
+                let boat_rect = egui::Rect::from_center_size(egui::pos2(pos.x,
+                                                                        pos.y + (header_height/2.0)),
                                                                         egui::vec2(boat_width, total_height));
                 
                 // Adding an interaction response to the boat area to allow dragging the boat around the frame
@@ -619,7 +1421,10 @@ impl eframe::App for RowingApp {
                                             egui::Sense::drag());
 
                 // Updating the boat's position based on the drag response
-                if resp.dragged() { boat.pos += resp.drag_delta(); }
+                if resp.dragged() {
+                    let delta = resp.drag_delta();
+                    boat.pos  = (pos.x + delta.x, pos.y + delta.y);
+                }
             }
         });
     }
@@ -630,77 +1435,352 @@ impl RowingApp {
 
     // --- METHOD TO ADD A NEW BOAT TO THE FRAME BASED ON THE SELECTED BOAT TYPE ---
     fn add_boat(&mut self, bt: BoatType) {
-        self.boats.push(Boat { 
-            boat_type: bt, 
-            seats: vec![None; bt.seat_count()], 
-            pos: egui::pos2(400.0, 300.0),
-            departure_time: String::new(),
-            destination: None,
-        });
+        self.state.session.boats.push(Boat::new(bt));
     }
 
     // --- ERROR CHECK METHOD FOR MISSING DEPARTURE TIMES, DESTINATIONS, OR UNFILLED SEATS ---
     fn validate_and_publish(&mut self) {
-        if self.boats.iter().any(|b| b.departure_time.trim().is_empty()) {
-            self.error_message = Some("Error: All boat departure times must be entered.".to_string());
-            return;
+        if self.validate_and_allocate() {
+            self.publish();
         }
-        if self.boats.iter().any(|b| b.destination.is_none()) {
-            self.error_message = Some("Error: All destination locations must be selected.".to_string());
-            return;
-        }
-        if self.boats.iter().any(|b| b.seats.iter().any(|s| s.is_none())) {
-            self.error_message = Some("Error: All boat seats must be filled.".to_string());
-            return;
+    }
+
+    // --- RUNS THE ALLOCATION INTO A FRESH TRANSPORT PLAN (LOCKED CHOICES ARE KEPT BY THE STRATEGY RUN) ---
+    fn validate_and_allocate(&mut self) -> bool {
+        if let Err(msg) = self.state.validate_boats(&self.state.session.boats) {
+            self.error_message = Some(msg);
+            return false;
         }
 
-        self.publish();
+        // Grouping people by their destination and departure time to prepare for the allocation algorithm
+        let groups = self.state.transport_requests(&self.state.session.boats);
+
+        // Handing off results of UI to other allocation algorithm, keeping a partial plan so it can be finished by hand
+        let rota    = self.fair_rota.then_some(&self.state.history);
+        let outcome = self.strategy.run(groups, &self.state.minibuses, &self.state.session, &self.state.travel_matrix(), rota);
+        let own_way = self.state.making_own_way(&self.state.session.boats);
+        let plan    = TransportPlan {
+            support_staff: self.state.support_trips(),
+            ..TransportPlan::from_result(outcome.result, outcome.diagnostics, own_way)
+        };
+        plan.sync_locks(&mut self.state.session.locks);
+        self.transport_plan = Some(plan);
+        true
     }
 
     // --- METHOD TO HANDLE THE LOGIC FOR PUBLISHING THE FINAL ALLOCATIONS AND GENERATING THE PDF ---
     fn publish(&mut self) {
+        let Some(plan) = &self.transport_plan else {
+            self.error_message = Some("Nothing to print yet - run the allocation first.".to_string());
+            return;
+        };
 
-        // Grouping people by their destination and departure time to prepare for the allocation algorithm
-        let mut groups: Vec<(Destination, String, Vec<Person>)> = Vec::new();
+        // If someone is still without a seat, the sheet is only printed when a partial plan is allowed
+        let report = plan.needs_transport_report();
+        if let Some(report) = &report && !self.publish_partial {
+            self.error_message = Some(report.clone());
+            return;
+        }
 
-        // Creating groups based on the boats currently on frame and grouping based on destination and departure time
-        for boat in &self.boats {
-            let dest = boat.destination.unwrap();
-            let time = boat.departure_time.clone();
-            
-            let people: Vec<Person> = boat.seats.iter()
-                .filter_map(|s| s.as_ref())
-                .filter_map(|id| self.state.get_person(id).cloned())
+        // The sheet is named and headed with the day of the outing, not the day it's printed
+        let on = self.state.session.outing_date();
+
+        if let Err(e) = crate::pdf::generate_pdf(plan, &self.state.session.boats, &self.state.people, &self.state.venues, &self.state.theme, on, &format!("transport_sheet_{}.pdf", on.format("%Y-%m-%d"))) {
+            self.error_message = Some(format!("PDF Generation failed: {}", e));             // Displaying an error message if PDF generation fails
+        } else {
+            let mut lines = match report {
+                None => vec!["PDF generated successfully.".to_string()],                    // Displaying a success message (using error message logic...)
+                Some(report) => vec!["Partial PDF generated - see \"Needs transport\".".to_string(), report],
+            };
+            lines.extend(plan.notes.iter().cloned());                                       // Plus the strategy's notes on how the allocation went
+
+            // Recording who drove, for the fair rota and the driving stats
+            if let Err(e) = self.state.record_drives(&plan.groups, on) {
+                lines.push(format!("Could not save the driving history: {}", e));
+            }
+            self.error_message = Some(lines.join("\n"));
+        }
+    }
+
+    // --- DRIVER SLIPS OR PERSONAL ITINERARIES FROM THE PLAN AS EDITED (THE SAME PLAN AS THE TRANSPORT SHEET) ---
+    fn publish_handouts(&mut self, slips: bool) {
+        let Some(plan) = &self.transport_plan else {
+            self.error_message = Some("Nothing to print yet - run the allocation first.".to_string());
+            return;
+        };
+
+        // Dated by the outing, like the transport sheet they go with
+        let on = self.state.session.outing_date();
+        let (filename, written) = if slips {
+            let filename = format!("driver_slips_{}.pdf", on.format("%Y-%m-%d"));
+            let written  = crate::pdf::generate_driver_slips(plan, &self.state.venues, &self.state.pickup_points, &self.state.theme, on, &filename);
+            (filename, written)
+        } else {
+            let filename = format!("itineraries_{}.pdf", on.format("%Y-%m-%d"));
+            let written  = crate::pdf::generate_itinerary_pdf(&itineraries(plan, &self.state.venues), &self.state.theme, on, &filename);
+            (filename, written)
+        };
+
+        self.error_message = Some(match written {
+            Ok(()) => format!("Wrote {}.", filename),
+            Err(e) => format!("PDF Generation failed: {}", e),
+        });
+    }
+
+    // --- WRITES THE PLAN AS EDITED IN THE CHOSEN EXPORT FORMAT, COPYING CHAT TEXT TO THE CLIPBOARD AS WELL ---
+    fn export_plan(&mut self, ui: &egui::Ui) {
+        let Some(plan) = &self.transport_plan else {
+            self.error_message = Some("Nothing to export yet - run the allocation first.".to_string());
+            return;
+        };
+
+        let format   = self.export_format;
+        let on       = self.state.session.outing_date();                                // The day being travelled, not the day it's exported
+        let text     = format.export(&plan.groups, &self.state.venues, on);
+        let filename = format!("transport_{}.{}", on.format("%Y-%m-%d"), format.extension());
+
+        if let Err(e) = std::fs::write(&filename, &text) {
+            self.error_message = Some(format!("Could not write {}: {}", filename, e));
+        } else if format == ExportFormat::Markdown {
+            ui.output_mut(|o| o.copied_text = text);
+            self.error_message = Some(format!("Wrote {} and copied it to the clipboard.", filename));
+        } else {
+            self.error_message = Some(format!("Wrote {}.", filename));
+        }
+    }
+
+    // --- TRANSPORT VIEW: ONE CARD PER VEHICLE, GROUPED BY DEPARTURE ---
+    // Passengers can be dragged between vehicles on the same departure, swapped with the driver, or locked in place
+    fn transport_view(&mut self, ui: &mut egui::Ui) {
+        let Some(plan) = &self.transport_plan else {
+            ui.label("No transport plan yet - press \"Re-run\" to allocate the crews on the lake.");
+            return;
+        };
+
+        let venues   = &self.state.venues;
+        let session  = &self.state.session;
+        let pointer  = ui.input(|i| i.pointer.interact_pos());
+        let released = ui.input(|i| i.pointer.any_released());
+        let locked   = |id: &str| session.lock_for(id).is_some();
+
+        let mut edit: Option<PlanEdit>     = None;
+        let mut drag_start: Option<Dragged> = None;
+        let mut drop_on: Option<usize>     = None;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+
+            // --- ANYONE STILL WITHOUT A SEAT (DRAG THEM ONTO A VEHICLE ON THEIR DEPARTURE) ---
+            if !plan.needs_transport.is_empty() {
+                egui::Frame::group(ui.style()).fill(egui::Color32::from_rgb(90, 60, 0)).show(ui, |ui| {
+                    ui.label(egui::RichText::new("Needs transport").strong().color(egui::Color32::from_rgb(255, 190, 0)));
+                    for (s_idx, shortfall) in plan.needs_transport.iter().enumerate() {
+                        for (p_idx, p) in shortfall.people.iter().enumerate() {
+                            let text = format!("{} ({} {})", p.name, shortfall.departure_time, shortfall.venue_name);
+                            if ui.add(egui::Label::new(text).sense(egui::Sense::drag())).drag_started() {
+                                drag_start = Some(Dragged::Stranded(s_idx, p_idx));
+                            }
+                        }
+                    }
+                });
+                ui.add_space(8.0);
+            }
+
+            // --- CREW MEMBERS TRAVELLING PRIVATELY (NOT EDITABLE HERE - MARK THE SEAT ON THE LAKE INSTEAD) ---
+            if !plan.making_own_way.is_empty() {
+                egui::CollapsingHeader::new(format!("Making own way ({})", plan.making_own_way.len())).show(ui, |ui| {
+                    for o in &plan.making_own_way {
+                        ui.label(format!("{} ({} {})", o.person.name, o.departure_time, o.venue_name));
+                    }
+                });
+                ui.add_space(8.0);
+            }
+
+            // --- SUPPORT STAFF (THEY'RE IN THE CARDS BELOW LIKE ANYONE ELSE, THIS IS JUST WHO THEY ARE) ---
+            if !plan.support_staff.is_empty() {
+                egui::CollapsingHeader::new(format!("Support staff ({})", plan.support_staff.len())).show(ui, |ui| {
+                    for s in &plan.support_staff {
+                        let vehicle = plan.outbound_vehicle(&s.person.student_id).map_or("no vehicle", |g| g.vehicle_label.as_str());
+                        ui.label(format!("{} - {} ({} {}) - {}", s.person.name, s.role.label(), s.departure_time, s.venue_name, vehicle));
+                    }
+                });
+                ui.add_space(8.0);
+            }
+
+            // --- OUTBOUND VEHICLES, ONE ROW OF CARDS PER DEPARTURE ---
+            let mut departures: Vec<(&str, &Destination)> = plan.groups.iter()
+                .filter(|g| g.leg == Leg::Outbound)
+                .map(|g| (g.departure_time.as_str(), &g.destination))
                 .collect();
+            departures.sort_by_key(|(time, dest)| (*time, venue_label(venues, dest)));
+            departures.dedup();
+
+            for (time, dest) in departures {
+                ui.heading(format!("{} {}", time, venue_label(venues, dest)));
+                ui.horizontal_wrapped(|ui| {
+                    for (g_idx, group) in plan.groups.iter().enumerate() {
+                        if group.leg != Leg::Outbound || group.departure_time != time || &group.destination != dest { continue; }
+
+                        let card = egui::Frame::group(ui.style()).show(ui, |ui| {
+                            ui.set_width(200.0);
+                            ui.label(egui::RichText::new(&group.vehicle_label).strong());
+                            ui.label(format!("{} of {} seats", group.passengers.len() + 1, group.capacity));
+                            if !group.equipment.is_empty() {
+                                let seats = match group.cargo_seats() { 0 => String::new(), n => format!(" ({} seat(s))", n) };
+                                ui.small(format!("Carrying {}{}", group.load_summary(), seats));
+                            }
 
-            if let Some(existing) = groups.iter_mut().find(|(d, t, _)| *d == dest && *t == time) {
-                existing.2.extend(people);
-            } else {
-                groups.push((dest, time, people));
+                            // Driver row
+                            ui.horizontal(|ui| {
+                                if ui.selectable_label(locked(&group.driver.student_id), "🔒").on_hover_text("Lock as this vehicle's driver").clicked() {
+                                    edit = Some(PlanEdit::ToggleLock(Lock { student_id: group.driver.student_id.clone(), registration: group.registration.clone(), driving: true }));
+                                }
+                                ui.label(egui::RichText::new(format!("Driver: {}", group.driver.name)).strong());
+                                let warnings = group.driver.driving_warnings(session.outing_date());
+                                if !warnings.is_empty() {
+                                    ui.colored_label(egui::Color32::from_rgb(230, 140, 0), "⚠").on_hover_text(warnings.join("\n"));
+                                }
+                            });
+
+                            // Passenger rows
+                            for (p_idx, p) in group.passengers.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    if ui.selectable_label(locked(&p.student_id), "🔒").on_hover_text("Lock in this vehicle").clicked() {
+                                        edit = Some(PlanEdit::ToggleLock(Lock { student_id: p.student_id.clone(), registration: group.registration.clone(), driving: false }));
+                                    }
+                                    let resp = ui.add(egui::Label::new(&p.name).sense(egui::Sense::drag()))
+                                        .on_hover_text("Drag onto another vehicle on this departure");
+                                    if resp.drag_started() {
+                                        drag_start = Some(Dragged::Passenger(g_idx, p_idx));
+                                    }
+                                    if ui.small_button("Drive").on_hover_text("Swap with the driver").clicked() {
+                                        edit = Some(PlanEdit::Drive(g_idx, p_idx));
+                                    }
+                                });
+                            }
+
+                            if !group.arrival_time.is_empty() {
+                                ui.small(format!("Arrive {}", group.arrival_time));
+                            }
+                        }).response;
+
+                        // Highlighting the card under a dragged person, and dropping them on it
+                        if self.dragging.is_some() && pointer.is_some_and(|pos| card.rect.contains(pos)) {
+                            ui.painter().rect_stroke(card.rect, 4.0, egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 190, 0)));
+                            if released { drop_on = Some(g_idx); }
+                        }
+                    }
+                });
+                ui.add_space(8.0);
+            }
+
+            // --- JOURNEYS HOME (PLANNED AGAIN FROM THE OUTBOUND VEHICLES AFTER EVERY CHANGE) ---
+            egui::CollapsingHeader::new("Journeys home").show(ui, |ui| {
+                for group in plan.groups.iter().filter(|g| g.leg == Leg::Return) {
+                    let names: Vec<&str> = group.passengers.iter().map(|p| p.name.as_str()).collect();
+                    ui.label(format!(
+                        "{} from {} - {} ({}): {}",
+                        group.departure_time, venue_label(venues, &group.destination), group.vehicle_label, group.driver.name, names.join(", "),
+                    ));
+                }
+            });
+
+            for note in &plan.notes {
+                ui.label(note);
+            }
+        });
+
+        // Name following the pointer while someone is being dragged
+        if let (Some(dragged), Some(pos)) = (self.dragging, pointer) {
+            let name = match dragged {
+                Dragged::Passenger(g_idx, p_idx) => &plan.groups[g_idx].passengers[p_idx].name,
+                Dragged::Stranded(s_idx, p_idx) => &plan.needs_transport[s_idx].people[p_idx].name,
+            };
+            egui::Area::new("dragged_person")
+                .order(egui::Order::Tooltip)
+                .fixed_pos(pos + egui::vec2(12.0, 12.0))
+                .show(ui.ctx(), |ui| ui.label(egui::RichText::new(name).strong()));
+        }
+
+        // --- APPLYING WHATEVER WAS DONE THIS FRAME ---
+        if let Some(dragged) = drag_start {
+            self.dragging = Some(dragged);
+        }
+        if released {
+            if let (Some(dragged), Some(to)) = (self.dragging, drop_on) {
+                edit = Some(match dragged {
+                    Dragged::Passenger(from, p_idx) => PlanEdit::Move(from, p_idx, to),
+                    Dragged::Stranded(s_idx, p_idx) => PlanEdit::Seat(s_idx, p_idx, to),
+                });
+            }
+            self.dragging = None;
+        }
+        if let Some(edit) = edit {
+            self.apply_plan_edit(edit);
+        }
+    }
+
+    // --- APPLIES ONE HAND EDIT, CHECKING CAPACITY AND LICENCES, AND KEEPS ANY LOCKS POINTING AT THE RIGHT VEHICLE ---
+    fn apply_plan_edit(&mut self, edit: PlanEdit) {
+        let Some(plan) = &mut self.transport_plan else { return };
+
+        if let PlanEdit::ToggleLock(lock) = edit {
+            let locks = &mut self.state.session.locks;
+            match locks.iter().position(|l| l.student_id == lock.student_id) {
+                Some(l_idx) => { locks.remove(l_idx); }
+                None => locks.push(lock),
             }
+            return;
         }
 
-        // Getting the current date for transport sheet creation and documentation
-        let curr_date = Local::now().format("%Y-%m-%d").to_string();
+        let result = {
+            let matrix  = self.state.travel_matrix();
+            let session = &self.state.session;
+            match edit {
+                PlanEdit::Move(from, p_idx, to) => plan.move_passenger(from, p_idx, to, session, &matrix),
+                PlanEdit::Seat(s_idx, p_idx, to) => plan.seat_stranded(s_idx, p_idx, to, session, &matrix),
+                PlanEdit::Drive(g_idx, p_idx) => plan.make_driver(g_idx, p_idx, session, &matrix),
+                PlanEdit::ToggleLock(_) => Ok(()),
+            }
+        };
 
-        // Handing off results of UI to other allocation algorithm and PDF generation,
-        match Allocation::assign_transport_global(groups, &self.state.minibuses, &self.wants_to_drive) {
+        match result {
+            Ok(()) => plan.sync_locks(&mut self.state.session.locks),
+            Err(msg) => self.error_message = Some(msg),
+        }
+    }
 
-            // If all allocations could be done successfully
-            Ok(all_allocations) => {
-                if let Err(e) = crate::pdf::generate_pdf(&all_allocations, &format!("transport_sheet_{}.pdf", curr_date)) {
-                    self.error_message = Some(format!("PDF Generation failed: {}", e));     // Displaying an error message if PDF generation fails
-                } else {
-                    self.error_message = Some("PDF generated successfully.".to_string());   // Displaying a success message (using error message logic...)
+    // --- SAVES THE SQUAD FILES, SAYING SO IF ONE WAS LEFT ALONE BECAUSE IT COULDN'T BE READ AT START UP ---
+    fn save_squad(&mut self) {
+        if let Err(e) = self.state.save_all() {
+            self.error_message = Some(format!("Could not save: {}", e));
+        }
+    }
+
+    // --- SAVES THE SESSION TO WHERE IT CAME FROM (OR ASKS FOR A NAME IF IT HAS NEVER BEEN SAVED) ---
+    fn save_session(&mut self) {
+        match self.state.session_file.clone() {
+            Some(file) => {
+                if let Err(e) = self.state.save_session(&file) {
+                    self.error_message = Some(format!("Could not save session: {}", e));
                 }
             }
+            None => self.save_as_name = Some(self.state.session.name.clone()),
+        }
+    }
+
+    // --- APPLY THE NAME FROM THE SAVE AS POPUP AND WRITE THE SESSION TO ITS NEW FILE ---
+    fn apply_save_as(&mut self) {
+        if let Some(name) = self.save_as_name.take() {
+            let name = name.trim().to_string();
+            if name.is_empty() {
+                self.error_message = Some("Please enter a session name.".to_string());
+                return;
+            }
 
-            // If there was an error during the allocation that caused someone to be missed
-            Err(unallocated_names) => {
-                self.error_message = Some(format!(
-                    "Error: Not everyone could be assigned -\n{}", 
-                    unallocated_names.join(",\n")
-                ));
+            self.state.session.name = name;
+            let file = SystemState::session_path(&self.state.session.name);
+            if let Err(e) = self.state.save_session(&file) {
+                self.error_message = Some(format!("Could not save session: {}", e));
             }
         }
     }
@@ -717,11 +1797,7 @@ impl RowingApp {
         if name.is_empty() || student_id.is_empty() { return None; }
         let year_of_entry: u16 = form.year_of_entry.trim().parse().ok()?; 
 
-        let pickup_locations: Vec<String> = form.pickup_locations           // Formatting entered pickup locations
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
+        let pickup_locations = form.pickup_locations.clone();               // Already restricted to known points by the picker
 
         let car = if form.has_car {                                         // Build car details only if the person has a car and all car fields are filled
             let seats: u8 = form.car_seats.trim().parse().ok()?;
@@ -729,6 +1805,7 @@ impl RowingApp {
                 vehicle_type: form.car_type.trim().to_string(),
                 registration: form.car_registration.trim().to_string(),
                 seats,
+                kit: form.car_kit.build(),
             })
         } else {
             None                                                            // Returns none if for some reason validation fails
@@ -740,8 +1817,10 @@ impl RowingApp {
             student_id,
             year_of_entry,
             pickup_locations,
+            phone: form.phone.trim().to_string(),
+            wants_to_drive: form.has_car && form.wants_to_drive,
             car,
-            can_drive_minibus: form.can_drive_minibus,
+            credentials: form.credentials.clone(),
         })
     }
 
@@ -749,14 +1828,14 @@ impl RowingApp {
     fn apply_edit_person(&mut self) {
 
         if let Some(form) = &self.edit_person_form {
+            if let Err(e) = form.credentials.validate() {
+                self.error_message = Some(e);                               // Leaves the window open so the date can be fixed
+                return;
+            }
 
             // Copying form data into local variables and formatting it before applying it back into system state
             let year_of_entry: u16 = form.year_of_entry.trim().parse().unwrap_or(0);
-            let pickup_locations: Vec<String> = form.pickup_locations
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
+            let pickup_locations = form.pickup_locations.clone();
 
             // Doing the same data input parsing but for the optional car fields
             let car = if form.has_car {
@@ -766,6 +1845,7 @@ impl RowingApp {
                     vehicle_type: form.car_type.trim().to_string(),
                     registration: form.car_registration.trim().to_string(),
                     seats,
+                    kit: form.car_kit.build(),
                 })
 
             } else {
@@ -775,22 +1855,28 @@ impl RowingApp {
             if let Some(gender) = form.gender.clone() {
                 let idx = form.index;
                 if idx < self.state.people.len() {                          // Safety check to ensure the index is within bounds of the people vector
+                    let old_id = self.state.people[idx].student_id.clone();
+
                     self.state.people[idx] = Person {                       // Applying edits from the form back into the main system state at the correct index
                         name: form.name.trim().to_string(),
                         gender,
                         student_id: form.student_id.trim().to_string(),
                         year_of_entry,
                         pickup_locations,
+                        phone: form.phone.trim().to_string(),
                         car,
-                        can_drive_minibus: form.can_drive_minibus,
+                        credentials: form.credentials.clone(),
+                        wants_to_drive: form.has_car && form.wants_to_drive,
                     };
-                    self.state.save_all().ok();                             // Saving the updated state back to the JSON files
+                    if let Err(e) = self.state.save_all() {                 // Saving the updated state back to the JSON files
+                        self.error_message = Some(format!("Could not save: {}", e));
+                    }
 
-                    let sid = self.state.people[idx].student_id.clone();    // Handling the "Wants to Drive" logic back to app-level
-                    if form.wants_to_drive && form.has_car {
-                        self.wants_to_drive.insert(sid);                    // Inserts student ID into the list of IDs that want to drive
-                    } else {
-                        self.wants_to_drive.remove(&sid);
+                    // Handling the per-session "Wants to Drive" override (keyed by the possibly edited student ID)
+                    let overrides = &mut self.state.session.driver_overrides;
+                    overrides.remove(&old_id);
+                    if let Some(drive) = form.session_drive {
+                        overrides.insert(self.state.people[idx].student_id.clone(), drive);
                     }
                 }
             }
@@ -807,10 +1893,174 @@ impl RowingApp {
                 self.state.minibuses[idx] = Minibus {                       // Updating edits from the form back into the main system state at the correct index
                     registration: form.registration.trim().to_string(),
                     seats,
+                    depot: form.depot.clone(),
+                    kit: form.kit.build(),
                 };
-                self.state.save_all().ok();
+                self.save_squad();
             }
         }
         self.edit_minibus_form = None;                                      // Closing the minibus edit window
     }
-}
\ No newline at end of file
+
+    // --- LICENCE CATEGORIES AND THE DATES THEY'RE CHECKED AGAINST, SHARED BY BOTH PERSON FORMS ---
+    fn credentials_fields(ui: &mut egui::Ui, creds: &mut DriverCredentials) {
+        ui.label("Licence Categories:");
+        ui.horizontal(|ui| {
+            for category in LicenceCategory::ALL {
+                let mut held = creds.has(category);
+                if ui.checkbox(&mut held, category.label()).changed() {
+                    creds.toggle(category);
+                }
+            }
+        });
+
+        // Dates are optional - blank ones are flagged as not recorded rather than blocking the save
+        let dates = [
+            ("Licence Held Since:",         &mut creds.licence_since),
+            ("Date of Birth:",              &mut creds.date_of_birth),
+            ("Minibus Assessment Date:",    &mut creds.assessment_date),
+            ("Insurance Expiry:",           &mut creds.insurance_expiry),
+        ];
+        for (label, date) in dates {
+            ui.label(label);
+            ui.add(egui::TextEdit::singleline(date).hint_text("YYYY-MM-DD"));
+        }
+    }
+
+    // --- TOW BAR AND BOOT SPACE INPUTS, SHARED BY THE CAR AND MINIBUS FORMS ---
+    fn haulage_fields(ui: &mut egui::Ui, form: &mut HaulageForm) {
+        ui.checkbox(&mut form.tow_capable, "Can tow a trailer");
+        ui.checkbox(&mut form.tow_bar_fitted, "Tow bar fitted");
+        ui.label("Cargo Slots:").on_hover_text("Boot space for blades, ergs and fuel - anything past it takes seats");
+        ui.text_edit_singleline(&mut form.cargo_slots);
+    }
+
+    // --- CHECKBOX LIST OF KNOWN PICKUP POINTS (ANY UNKNOWN ONES LEFT OVER FROM OLD DATA ARE SHOWN IN RED TO UNTICK) ---
+    fn pickup_picker(ui: &mut egui::Ui, points: &[PickupPoint], selected: &mut Vec<String>) {
+        for point in points {
+            let mut ticked = selected.contains(&point.name);
+            if ui.checkbox(&mut ticked, &point.name).on_hover_text(&point.notes).changed() {
+                if ticked {
+                    selected.push(point.name.clone());
+                } else {
+                    selected.retain(|n| n != &point.name);
+                }
+            }
+        }
+
+        selected.retain(|name| {
+            if points.iter().any(|p| &p.name == name) { return true; }
+            let mut ticked = true;
+            ui.checkbox(&mut ticked, egui::RichText::new(format!("{} (unknown)", name)).color(egui::Color32::RED));
+            ticked
+        });
+    }
+
+    // --- APPLY EDITS FROM THE EDIT PICKUP POINT FORM BACK INTO STATE (RENAMES ARE CARRIED THROUGH TO PEOPLE AND DEPOTS) ---
+    fn apply_edit_pickup(&mut self) {
+        if let Some(form) = &self.edit_pickup_form {
+            let name = form.name.trim().to_string();
+            if name.is_empty() {
+                self.error_message = Some("Please enter a pickup point name.".to_string());
+                return;
+            }
+
+            let old_name = form.index.and_then(|i| self.state.pickup_points.get(i)).map(|p| p.name.clone());
+            if old_name.as_ref() != Some(&name) && self.state.is_pickup_point(&name) {
+                self.error_message = Some(format!("A pickup point called '{}' already exists.", name));
+                return;
+            }
+
+            let point = PickupPoint {
+                name: name.clone(),
+                latitude: form.latitude.trim().parse().unwrap_or(0.0),
+                longitude: form.longitude.trim().parse().unwrap_or(0.0),
+                notes: form.notes.trim().to_string(),
+            };
+
+            match form.index {
+                Some(idx) if idx < self.state.pickup_points.len() => self.state.pickup_points[idx] = point,
+                _ => self.state.pickup_points.push(point),
+            }
+
+            // Carrying a rename through to everyone (and every minibus and boat drop-off) that used the old name
+            if let Some(old_name) = old_name.filter(|o| o != &name) {
+                for loc in self.state.people.iter_mut().flat_map(|p| p.pickup_locations.iter_mut()) {
+                    if *loc == old_name { *loc = name.clone(); }
+                }
+                for mb in self.state.minibuses.iter_mut().filter(|m| m.depot == old_name) {
+                    mb.depot = name.clone();
+                }
+                for drop_off in self.state.session.boats.iter_mut().filter_map(|b| b.drop_off.as_mut()) {
+                    if *drop_off == old_name { *drop_off = name.clone(); }
+                }
+            }
+            self.save_squad();
+        }
+        self.edit_pickup_form = None;
+    }
+
+    // --- REMOVES THE PICKUP POINT BEING EDITED (ONLY IF NOBODY AND NO MINIBUS STILL USES IT) ---
+    fn delete_edit_pickup(&mut self) {
+        if let Some(idx) = self.edit_pickup_form.as_ref().and_then(|f| f.index)
+            && idx < self.state.pickup_points.len() {
+            let name  = &self.state.pickup_points[idx].name;
+            let users = self.state.people.iter().filter(|p| p.pickup_locations.contains(name)).count()
+                      + self.state.minibuses.iter().filter(|m| &m.depot == name).count();
+
+            if users > 0 {
+                self.error_message = Some(format!("'{}' is still used by {} people / minibuses.", name, users));
+                return;
+            }
+            self.state.pickup_points.remove(idx);
+            self.save_squad();
+        }
+        self.edit_pickup_form = None;
+    }
+
+    // --- APPLY EDITS FROM THE EDIT VENUE FORM BACK INTO STATE (ADDING A NEW VENUE IF IT HAS NO INDEX) ---
+    fn apply_edit_venue(&mut self) {
+        if let Some(form) = &self.edit_venue_form {
+            let name = form.name.trim().to_string();
+            if name.is_empty() {
+                self.error_message = Some("Please enter a venue name.".to_string());
+                return;
+            }
+
+            // Parsing the numeric inputs (or 0 if they fail) the same way as the minibus form
+            let latitude: f64        = form.latitude.trim().parse().unwrap_or(0.0);
+            let longitude: f64       = form.longitude.trim().parse().unwrap_or(0.0);
+            let travel_minutes: u32  = form.travel_minutes.trim().parse().unwrap_or(0);
+            let session_minutes: u32 = form.session_minutes.trim().parse().unwrap_or(Venue::default_session_minutes());
+
+            // Existing venues keep their ID so saved sessions still point at them after a rename
+            let id = match form.index {
+                Some(idx) if idx < self.state.venues.len() => self.state.venues[idx].id.clone(),
+                _ => Venue::id_from_name(&name),
+            };
+
+            if form.index.is_none() && (id.0.is_empty() || self.state.venues.iter().any(|v| v.id == id)) {
+                self.error_message = Some(format!("A venue called '{}' already exists.", name));
+                return;
+            }
+
+            let venue = Venue { id, name, colour: form.colour, address: form.address.trim().to_string(), latitude, longitude, travel_minutes, session_minutes };
+            match form.index {
+                Some(idx) if idx < self.state.venues.len() => self.state.venues[idx] = venue,
+                _ => self.state.venues.push(venue),
+            }
+            self.save_squad();
+        }
+        self.edit_venue_form = None;                                        // Closing the venue window after applying edits
+    }
+
+    // --- REMOVES THE VENUE BEING EDITED (BOATS STILL POINTING AT IT WILL FAIL VALIDATION UNTIL CHANGED) ---
+    fn delete_edit_venue(&mut self) {
+        if let Some(idx) = self.edit_venue_form.as_ref().and_then(|f| f.index)
+            && idx < self.state.venues.len() {
+            self.state.venues.remove(idx);
+            self.save_squad();
+        }
+        self.edit_venue_form = None;
+    }
+}